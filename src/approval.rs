@@ -1,5 +1,7 @@
 use crate::*;
-use near_sdk::{ext_contract};
+use near_sdk::{ext_contract, Gas};
+
+const GAS_FOR_NFT_APPROVE: Gas = Gas(10_000_000_000_000);
 
 pub trait NonFungibleTokenCore {
     //approve an account ID to transfer a token on your behalf
@@ -84,14 +86,16 @@ impl NonFungibleTokenCore for Contract {
         //if some message was passed into the function, we initiate a cross contract call on the
         //account we're giving access to. 
         if let Some(msg) = msg {
-            // Defaulting GAS weight to 1, no attached deposit, and no static GAS to attach.
-            ext_non_fungible_approval_receiver::ext(account_id)
-                .nft_on_approve(
-                    token_id, 
-                    token.owner_id, 
-                    approval_id, 
-                    msg
-                ).as_return();
+            // No attached deposit and all the remaining GAS minus the GAS needed to finish the approval.
+            ext_non_fungible_approval_receiver::nft_on_approve(
+                token_id, 
+                token.owner_id, 
+                approval_id, 
+                msg,
+                account_id, //contract account to make the call to
+                NO_DEPOSIT, //attached deposit
+                env::prepaid_gas() - GAS_FOR_NFT_APPROVE, //attached GAS
+            ).as_return();
         }
     }
 
@@ -0,0 +1,188 @@
+use crate::*;
+
+const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+//boost multiplier (basis points, 10000 = 1x) applied from a lock duration
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockMultiplier {
+    //minimum time (in nanoseconds) the bond has to stay locked
+    pub min_lock: U64,
+    pub multiplier: u32,
+}
+
+//rules of the reputation bonds
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BondConfig {
+    //score added for each NEAR bonded
+    pub points_per_near: u64,
+    //the multiplier with the highest min_lock reached is applied to the boost
+    #[serde(default)]
+    pub lock_multipliers: Vec<LockMultiplier>,
+    //maximum boost of a bond
+    pub max_boost: u64,
+    //time (in nanoseconds) an unbonded amount waits before it can be withdrawn, it can still be slashed meanwhile
+    pub unbonding_delay: U64,
+}
+
+impl Default for BondConfig {
+    fn default() -> Self {
+        Self {
+            points_per_near: 1,
+            lock_multipliers: vec![],
+            max_boost: 50,
+            unbonding_delay: U64(7 * 24 * 60 * 60 * 1_000_000_000),
+        }
+    }
+}
+
+impl BondConfig {
+    //get the boost of a bond of the passed in amount locked for the passed in time
+    pub(crate) fn boost(&self, amount: Balance, lock: u64) -> u64 {
+        let multiplier = self
+            .lock_multipliers
+            .iter()
+            .filter(|lock_multiplier| lock_multiplier.min_lock.0 <= lock)
+            .max_by_key(|lock_multiplier| lock_multiplier.min_lock.0)
+            .map(|lock_multiplier| lock_multiplier.multiplier)
+            .unwrap_or(DEFAULT_CATEGORY_WEIGHT);
+        let boost = amount * self.points_per_near as u128 / ONE_NEAR * multiplier as u128
+            / DEFAULT_CATEGORY_WEIGHT as u128;
+        std::cmp::min(boost, self.max_boost as u128) as u64
+    }
+}
+
+//NEAR locked by an account to boost its reputation
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bond {
+    pub amount: U128,
+    //block timestamp (in nanoseconds) until the bond can't be unbonded
+    pub locked_until: U64,
+    //score added to the reputation of the account
+    pub boost: u64,
+}
+
+//an amount waiting in the unbonding queue
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Unbonding {
+    pub amount: U128,
+    //block timestamp (in nanoseconds) from which the amount can be withdrawn
+    pub available_at: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    //change the rules of the bonds, only the governance account (or the owner if there is none) can call it. Bonds already made keep their boost
+    pub fn set_bond_config(&mut self, config: BondConfig) {
+        self.assert_governance();
+        self.bond_config = config;
+    }
+
+    pub fn get_bond_config(&self) -> BondConfig {
+        self.bond_config.clone()
+    }
+
+    // Method to lock the attached NEAR for lock_duration to boost the reputation of the account.
+    // Bonding again adds to the bond, keeps the longest lock and recalculates the boost.
+    // The storage of the bond is charged against the storage balance of the account
+    #[payable]
+    pub fn bond_reputation(&mut self, lock_duration: U64) -> Bond {
+        let initial_storage_usage = env::storage_usage();
+        let account_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let now = env::block_timestamp();
+        assert!(deposit > 0, "Attach the NEAR to bond");
+
+        let previous = self.bonds.get(&account_id);
+        let previous_boost = previous.as_ref().map(|bond| bond.boost).unwrap_or(0);
+        let amount = previous.as_ref().map(|bond| bond.amount.0).unwrap_or(0) + deposit;
+        let locked_until = std::cmp::max(
+            previous.as_ref().map(|bond| bond.locked_until.0).unwrap_or(0),
+            now + lock_duration.0,
+        );
+        let boost = self.bond_config.boost(amount, locked_until - now);
+
+        self.internal_update_por(&account_id, ReputationReason::Bonded, None, |por| {
+            por.deduct(previous_boost);
+            por.score += boost;
+        })
+        .expect("The account has no reputation to boost");
+
+        let bond = Bond { amount: U128(amount), locked_until: U64(locked_until), boost };
+        self.bonds.insert(&account_id, &bond);
+
+        let required_storage_in_bytes = env::storage_usage().saturating_sub(initial_storage_usage);
+        self.internal_charge_storage(&account_id, required_storage_in_bytes);
+        bond
+    }
+
+    // Method to unbond the whole bond once its lock has finished. The boost is removed right away
+    // and the amount goes to the unbonding queue, where it waits for the unbonding delay
+    pub fn unbond_reputation(&mut self) -> Unbonding {
+        let account_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        let bond = self.bonds.get(&account_id).expect("The account has no bond");
+        assert!(now >= bond.locked_until.0, "The bond is still locked");
+
+        self.bonds.remove(&account_id);
+        self.internal_update_por(&account_id, ReputationReason::Unbonded, None, |por| por.deduct(bond.boost))
+            .expect("The account has no reputation");
+
+        let unbonding = Unbonding {
+            amount: bond.amount,
+            available_at: U64(now + self.bond_config.unbonding_delay.0),
+        };
+        let mut unbondings = self.unbondings.get(&account_id).unwrap_or_default();
+        unbondings.push(unbonding.clone());
+        self.unbondings.insert(&account_id, &unbondings);
+        unbonding
+    }
+
+    //transfer to the account the unbonded amounts whose delay has finished, returns the amount withdrawn
+    pub fn withdraw_unbonded(&mut self) -> U128 {
+        let account_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+        let unbondings = self.unbondings.get(&account_id).unwrap_or_default();
+
+        let (available, waiting): (Vec<Unbonding>, Vec<Unbonding>) =
+            unbondings.into_iter().partition(|unbonding| unbonding.available_at.0 <= now);
+        let amount: Balance = available.iter().map(|unbonding| unbonding.amount.0).sum();
+        assert!(amount > 0, "There is nothing to withdraw");
+
+        if waiting.is_empty() {
+            self.unbondings.remove(&account_id);
+        } else {
+            self.unbondings.insert(&account_id, &waiting);
+        }
+        Promise::new(account_id).transfer(amount);
+        U128(amount)
+    }
+
+    pub fn get_bond(&self, account_id: AccountId) -> Option<Bond> {
+        self.bonds.get(&account_id)
+    }
+
+    //get the amounts of the account waiting in the unbonding queue
+    pub fn get_unbondings(&self, account_id: AccountId) -> Vec<Unbonding> {
+        self.unbondings.get(&account_id).unwrap_or_default()
+    }
+}
+
+impl Contract {
+    //take the bond and the unbonding amounts of the account, removing the boost from its reputation. Returns the amount taken
+    pub(crate) fn internal_slash_bond(&mut self, account_id: &AccountId) -> Balance {
+        let mut slashed = 0;
+        if let Some(bond) = self.bonds.remove(account_id) {
+            self.internal_update_por(account_id, ReputationReason::BondSlashed, None, |por| por.deduct(bond.boost))
+                .expect("The account has no reputation");
+            slashed += bond.amount.0;
+        }
+        if let Some(unbondings) = self.unbondings.remove(account_id) {
+            slashed += unbondings.iter().map(|unbonding| unbonding.amount.0).sum::<Balance>();
+        }
+        slashed
+    }
+}
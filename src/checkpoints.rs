@@ -0,0 +1,84 @@
+use crate::*;
+
+//the score of an account from a block timestamp on
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ScoreCheckpoint {
+    pub timestamp: U64,
+    pub score: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    //get the score the account had at the passed in block timestamp (in nanoseconds), 0 if it had no reputation yet.
+    //Changes made in the block of the timestamp are included
+    pub fn get_score_at(&self, account_id: AccountId, timestamp: U64) -> u64 {
+        let checkpoints = match self.score_checkpoints.get(&account_id) {
+            Some(checkpoints) => checkpoints,
+            None => return 0,
+        };
+
+        //binary search of the number of checkpoints written up to the timestamp
+        let (mut low, mut high) = (0, checkpoints.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if checkpoints.get(middle).unwrap().timestamp.0 <= timestamp.0 {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        match low {
+            0 => 0,
+            _ => checkpoints.get(low - 1).unwrap().score,
+        }
+    }
+}
+
+impl Contract {
+    //write a checkpoint with the new score of the account, a change in the same block replaces the checkpoint of the block.
+    //The first change of a reputation migrated from the first deployment, if it comes before migrate_tokens reaches it,
+    //also writes its previous score as a starting checkpoint
+    pub(crate) fn internal_checkpoint_score(&mut self, account_id: &AccountId, previous_score: u64, score: u64) {
+        if !self.score_checkpoints.contains_key(account_id) && previous_score > 0 {
+            self.internal_start_checkpoints(account_id, previous_score);
+        }
+        let mut checkpoints = self.score_checkpoints.get(account_id).unwrap_or_else(|| {
+            //if the account doesn't have checkpoints, we create a new vector
+            Vector::new(
+                StorageKey::ScoreCheckpointsInner {
+                    //we get a new unique prefix for the collection
+                    account_id_hash: hash_account_id(account_id),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+
+        let checkpoint = ScoreCheckpoint { timestamp: U64(env::block_timestamp()), score };
+        match checkpoints.len().checked_sub(1) {
+            Some(last) if checkpoints.get(last).unwrap().timestamp == checkpoint.timestamp => {
+                checkpoints.replace(last, &checkpoint);
+            }
+            _ => checkpoints.push(&checkpoint),
+        }
+        self.score_checkpoints.insert(account_id, &checkpoints);
+    }
+
+    //write the first checkpoint of a reputation migrated from the first deployment, which had no checkpoints. Its score
+    //is taken from the time of its last quest, the only change of the score that its Proof of Reputation records
+    pub(crate) fn internal_start_checkpoints(&mut self, account_id: &AccountId, score: u64) {
+        let timestamp = match self.internal_get_por(account_id) {
+            Some((_, por)) => por.date_last_quest,
+            None => return,
+        };
+        let mut checkpoints = Vector::new(
+            StorageKey::ScoreCheckpointsInner { account_id_hash: hash_account_id(account_id) }
+                .try_to_vec()
+                .unwrap(),
+        );
+        checkpoints.push(&ScoreCheckpoint { timestamp: U64(timestamp), score });
+        self.score_checkpoints.insert(account_id, &checkpoints);
+    }
+}
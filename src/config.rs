@@ -0,0 +1,34 @@
+use crate::*;
+
+//the settings of the contract as returned from get_config
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractConfig {
+    pub owner_id: AccountId,
+    //DAO that makes the quest registry and scoring changes instead of the owner, if any
+    pub governance_account: Option<AccountId>,
+    pub treasury_id: AccountId,
+    pub token_id_scheme: TokenIdScheme,
+    //if the score of accounts without an I-Am-Human proof is held as pending
+    pub human_gating: bool,
+    //issuers and classes of SBTs accepted as proof of humanity
+    pub human_proof_issuers: Vec<HumanProofIssuer>,
+    pub vouch_config: VouchConfig,
+    pub bond_config: BondConfig,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn get_config(&self) -> ContractConfig {
+        ContractConfig {
+            owner_id: self.owner_id.clone(),
+            governance_account: self.governance_account.clone(),
+            treasury_id: self.treasury_id.clone(),
+            token_id_scheme: self.token_id_scheme,
+            human_gating: self.human_gating,
+            human_proof_issuers: self.human_proof_issuers.clone(),
+            vouch_config: self.vouch_config.clone(),
+            bond_config: self.bond_config.clone(),
+        }
+    }
+}
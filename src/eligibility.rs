@@ -0,0 +1,89 @@
+use crate::*;
+
+//the answer of a dry run of a quest claim
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EligibilityReport {
+    pub quest_id: u64,
+    //the claim would mint the quest (or complete it again) right now
+    pub eligible: bool,
+    //value returned by the verification call and the minimum the quest requires, None for quests awarded by issuers or oracles
+    pub observed_value: Option<U128>,
+    pub threshold: Option<U128>,
+    pub missing_prerequisites: Vec<u64>,
+    //the reason why the account is not eligible
+    pub error: Option<ClaimError>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // Dry run of nft_mint_quest: makes the same verification call without minting or charging storage.
+    // Quests awarded by issuers or oracles only get the checks done by this contract.
+    pub fn check_quest_eligibility(&mut self, account_id: AccountId, quest_id: u64) -> PromiseOrValue<EligibilityReport> {
+        let quest = match self.quests.get(&quest_id) {
+            Some(quest) => quest,
+            None => return PromiseOrValue::Value(EligibilityReport {
+                quest_id,
+                eligible: false,
+                observed_value: None,
+                threshold: None,
+                missing_prerequisites: vec![],
+                error: Some(ClaimError::QuestNotFound),
+            }),
+        };
+
+        let completed = self.internal_completed_quests(&account_id);
+        let missing_prerequisites = self.internal_missing_prerequisites(&completed, &quest);
+        let error = if !quest.active {
+            Some(ClaimError::QuestInactive)
+        } else if !self.internal_is_in_season(quest_id) {
+            Some(ClaimError::OutOfSeason)
+        } else {
+            self.internal_claim_error(&account_id, quest_id, &quest)
+        };
+
+        match quest.verifier {
+            QuestVerifier::Issuer | QuestVerifier::Oracle => PromiseOrValue::Value(EligibilityReport {
+                quest_id,
+                eligible: error.is_none(),
+                observed_value: None,
+                threshold: None,
+                missing_prerequisites,
+                error,
+            }),
+            _ => verification_call(&quest.verifier, &account_id)
+                .then(ext_self::resolve_eligibility(
+                    quest_id,
+                    account_id,
+                    missing_prerequisites,
+                    error,
+                    env::current_account_id(),
+                    NO_DEPOSIT,
+                    GAS_FOR_QUEST_CHECK,
+                ))
+                .into(),
+        }
+    }
+
+    //build the report of check_quest_eligibility with the value returned by the verification call
+    #[private]
+    pub fn resolve_eligibility(&self, quest_id: u64, account_id: AccountId, missing_prerequisites: Vec<u64>, error: Option<ClaimError>) -> EligibilityReport {
+        assert_self();
+        let quest = self.quests.get(&quest_id).expect("Quest not found");
+        let observed_value = quest_check_result().and_then(|result| self.internal_observed_value(&quest, &account_id, &result));
+        let error = match observed_value {
+            None => error.or(Some(ClaimError::VerifierFailed)),
+            Some(value) if value < quest.threshold() => error.or(Some(ClaimError::NotEligible)),
+            Some(_) => error,
+        };
+
+        EligibilityReport {
+            quest_id,
+            eligible: error.is_none(),
+            observed_value: observed_value.map(U128),
+            threshold: Some(U128(quest.threshold())),
+            missing_prerequisites,
+            error,
+        }
+    }
+}
@@ -1,5 +1,53 @@
 use crate::*;
 
+//which tokens of an owner are returned by nft_tokens_for_owner_page
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenKind {
+    //badges of completed quests
+    Quest,
+    //the Proof of Reputation token
+    Por,
+}
+
+//filter of the tokens of an owner, every field that is set must match
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenFilter {
+    #[serde(default)]
+    pub kind: Option<TokenKind>,
+    //only quest badges of the passed in category
+    #[serde(default)]
+    pub category: Option<QuestCategory>,
+    //only quest badges of the season earned while it was running
+    #[serde(default)]
+    pub season_id: Option<u64>,
+}
+
+//a page of tokens and the cursor to pass in to get the next one, None when there are no more tokens
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenPage {
+    pub tokens: Vec<JsonToken>,
+    pub next_cursor: Option<String>,
+}
+
+//key of a token ID in the sorted token IDs, sorted by length and then value so numeric IDs are sorted by their value
+pub(crate) fn token_sort_key(token_id: &str) -> (u64, TokenId) {
+    (token_id.len() as u64, token_id.to_string())
+}
+
+//key of a token ID in the sorted tokens of its owner
+pub(crate) fn owner_sort_key(account_id: &AccountId, token_id: &str) -> (AccountId, u64, TokenId) {
+    (account_id.clone(), token_id.len() as u64, token_id.to_string())
+}
+
+//key of a token ID in the sorted tokens of its quest
+pub(crate) fn quest_sort_key(quest_id: u64, token_id: &str) -> (u64, u64, TokenId) {
+    (quest_id, token_id.len() as u64, token_id.to_string())
+}
+
 #[near_bindgen]
 impl Contract {
     //Query for the total supply of NFTs on the contract
@@ -20,7 +68,7 @@ impl Contract {
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
             .take(limit.unwrap_or(50) as usize) 
             //we'll map the token IDs which are strings into Json Tokens
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .filter_map(|token_id| self.nft_token(token_id))
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
@@ -69,8 +117,153 @@ impl Contract {
             //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
             .take(limit.unwrap_or(50) as usize) 
             //we'll map the token IDs which are strings into Json Tokens
-            .map(|token_id| self.nft_token(token_id.clone()).unwrap())
+            .filter_map(|token_id| self.nft_token(token_id))
             //since we turned the keys into an iterator, we need to turn it back into a vector to return
             .collect()
     }
+
+    //get the number of tokens minted for a quest
+    pub fn nft_supply_for_quest(&self, quest_id: u64) -> U128 {
+        //if there is some set of tokens, we'll return the length as a U128
+        self.tokens_per_type
+            .get(&quest_id)
+            .map(|tokens| U128(tokens.len() as u128))
+            .unwrap_or(U128(0))
+    }
+
+    //Query for the tokens of a quest using a cursor, sorted by token ID, to list the accounts that completed it.
+    //The cursor is the last token ID of the previous page so mints and burns in between don't skip or repeat tokens
+    pub fn nft_tokens_by_quest(
+        &self,
+        quest_id: u64,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> TokenPage {
+        self.internal_token_page(
+            limit,
+            |last| {
+                let key = match last.or(cursor.as_deref()) {
+                    Some(last) => self.sorted_tokens_per_quest.higher(&quest_sort_key(quest_id, last)),
+                    None => self.sorted_tokens_per_quest.ceil_key(&(quest_id, 0, String::new())),
+                };
+                key.filter(|key| key.0 == quest_id).map(|key| key.2)
+            },
+            |_| true,
+        )
+    }
+
+    //Query for nft tokens on the contract using a cursor, sorted by token ID. The cursor is the last token ID
+    //of the previous page so mints and burns in between don't skip or repeat tokens, and each page only reads
+    //the tokens it returns
+    pub fn nft_tokens_page(&self, cursor: Option<String>, limit: Option<u64>) -> TokenPage {
+        let mut key = match cursor {
+            Some(cursor) => self.sorted_token_ids.higher(&token_sort_key(&cursor)),
+            None => self.sorted_token_ids.min(),
+        };
+
+        let mut tokens = vec![];
+        let mut last = None;
+        for _ in 0..limit.unwrap_or(50) {
+            let (length, token_id) = match key {
+                Some(key) => key,
+                None => break,
+            };
+            if let Some(token) = self.nft_token(token_id.clone()) {
+                tokens.push(token);
+            }
+            key = self.sorted_token_ids.higher(&(length, token_id.clone()));
+            last = Some(token_id);
+        }
+        TokenPage {
+            tokens,
+            next_cursor: key.and(last),
+        }
+    }
+
+    //Query for the tokens of an owner that match the filter, sorted by token ID. The cursor is the last
+    //token ID of the previous page so transfers in between don't skip or repeat tokens
+    pub fn nft_tokens_for_owner_page(
+        &self,
+        account_id: AccountId,
+        filter: Option<TokenFilter>,
+        cursor: Option<String>,
+        limit: Option<u64>,
+    ) -> TokenPage {
+        let filter = filter.unwrap_or_default();
+        self.internal_token_page(
+            limit,
+            |last| {
+                let key = match last.or(cursor.as_deref()) {
+                    Some(last) => self.sorted_tokens_per_owner.higher(&owner_sort_key(&account_id, last)),
+                    None => self.sorted_tokens_per_owner.ceil_key(&(account_id.clone(), 0, String::new())),
+                };
+                key.filter(|key| key.0 == account_id).map(|key| key.2)
+            },
+            |token_id| self.internal_token_matches(token_id, &filter),
+        )
+    }
+}
+
+impl Contract {
+    //build a page of the tokens that match, next_token returns the token after the passed in one (or the first token
+    //when None is passed in) so each page only reads its own tokens
+    fn internal_token_page(
+        &self,
+        limit: Option<u64>,
+        next_token: impl Fn(Option<&str>) -> Option<TokenId>,
+        matches: impl Fn(&TokenId) -> bool,
+    ) -> TokenPage {
+        let limit = limit.unwrap_or(50) as usize;
+        let mut tokens = vec![];
+        let mut next_cursor = None;
+        let mut token_id = next_token(None);
+        while let Some(current) = token_id {
+            if tokens.len() == limit {
+                next_cursor = tokens.last().map(|token: &JsonToken| token.token_id.clone());
+                break;
+            }
+            if matches(&current) {
+                if let Some(token) = self.nft_token(current.clone()) {
+                    tokens.push(token);
+                }
+            }
+            token_id = next_token(Some(&current));
+        }
+        TokenPage { tokens, next_cursor }
+    }
+
+    //check if a token matches every field of the filter that is set
+    fn internal_token_matches(&self, token_id: &TokenId, filter: &TokenFilter) -> bool {
+        let quest_id = self.internal_quest_id_of(token_id);
+        let kind = if quest_id.is_some() { TokenKind::Quest } else { TokenKind::Por };
+        if filter.kind.is_some_and(|filter_kind| filter_kind != kind) {
+            return false;
+        }
+        if filter.category.is_none() && filter.season_id.is_none() {
+            return true;
+        }
+
+        //the category and season filters only match quest badges
+        let quest_id = match quest_id {
+            Some(quest_id) => quest_id,
+            None => return false,
+        };
+        if let Some(category) = filter.category {
+            if self.quests.get(&quest_id).map(|quest| quest.category) != Some(category) {
+                return false;
+            }
+        }
+        if let Some(season_id) = filter.season_id {
+            let season = match self.seasons.get(&season_id) {
+                Some(season) => season,
+                None => return false,
+            };
+            let metadata = self.token_metadata_by_id.get(token_id).unwrap();
+            let quest: Quest = from_extra(metadata.extra.as_ref().unwrap());
+            return season.quest_ids.contains(&quest_id)
+                && season.starts_at.0 <= quest.date
+                && quest.date <= season.ends_at.0;
+        }
+        true
+    }
 }
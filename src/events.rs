@@ -2,8 +2,11 @@ use std::fmt;
 
 use near_sdk::serde::{Deserialize, Serialize};
 
+use crate::ClaimError;
+
 /// Enum that represents the data type of the EventLog.
-/// The enum can either be an NftMint or an NftTransfer.
+/// The enum can either be an NftMint, an NftTransfer or an NftBurn (nep171), or one of the
+/// events of the Proof of Reputation contract.
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(tag = "event", content = "data")]
 #[serde(rename_all = "snake_case")]
@@ -12,6 +15,8 @@ use near_sdk::serde::{Deserialize, Serialize};
 pub enum EventLogVariant {
     NftMint(Vec<NftMintLog>),
     NftTransfer(Vec<NftTransferLog>),
+    NftBurn(Vec<NftBurnLog>),
+    QuestClaimFailed(Vec<QuestClaimFailedLog>),
 }
 
 /// Interface to capture data about an event
@@ -78,6 +83,41 @@ pub struct NftTransferLog {
     pub memo: Option<String>,
 }
 
+/// An event log to capture token burning
+///
+/// Arguments
+/// * `owner_id`: owner of tokens to burn
+/// * `authorized_id`: approved account to burn, if applicable
+/// * `token_ids`: ["1","2"]
+/// * `memo`: optional message
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftBurnLog {
+    pub owner_id: String,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub authorized_id: Option<String>,
+
+    pub token_ids: Vec<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+}
+
+/// An event log to capture a quest claim that did not mint anything
+///
+/// Arguments
+/// * `account_id`: "user.near"
+/// * `quest_id`: 1
+/// * `error`: "not_eligible"
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestClaimFailedLog {
+    pub account_id: String,
+    pub quest_id: u64,
+    pub error: ClaimError,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,4 +175,19 @@ mod tests {
         };
         assert_eq!(expected, log.to_string());
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn por_format_quest_claim_failed() {
+        let expected = r#"EVENT_JSON:{"standard":"por","version":"1.0.0","event":"quest_claim_failed","data":[{"account_id":"user1.near","quest_id":1,"error":"not_eligible"}]}"#;
+        let log = EventLog {
+            standard: "por".to_string(),
+            version: "1.0.0".to_string(),
+            event: EventLogVariant::QuestClaimFailed(vec![QuestClaimFailedLog {
+                account_id: "user1.near".to_string(),
+                quest_id: 1,
+                error: ClaimError::NotEligible,
+            }]),
+        };
+        assert_eq!(expected, log.to_string());
+    }
+}
@@ -0,0 +1,105 @@
+use crate::*;
+
+//score of a quest held until the account proves it is human
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct PendingScore {
+    pub category: QuestCategory,
+    pub score: u64,
+    //score held for each season that was open when the quest was completed, added to the season when it is released
+    pub seasons: BTreeMap<u64, u64>,
+}
+
+#[near_bindgen]
+impl Contract {
+    //turn the human gating policy on or off, only the governance account (or the owner if there is none) can call it. While it is on, the score of quests
+    //completed by accounts without an I-Am-Human proof is held as pending until the account proves it is human
+    pub fn set_human_gating(&mut self, enabled: bool) {
+        self.assert_governance();
+        self.human_gating = enabled;
+    }
+
+    //get the score of each category held until the account proves it is human
+    pub fn get_pending_score(&self, account_id: AccountId) -> BTreeMap<QuestCategory, u64> {
+        let mut categories = BTreeMap::new();
+        for held in self.pending_scores.get(&account_id).unwrap_or_default().values() {
+            *categories.entry(held.category).or_insert(0) += held.score;
+        }
+        categories
+    }
+
+    //release the pending score of an account into its Proof of Reputation and seasons and transfer its held rewards.
+    //It can be called by anyone once the account is a verified human or the policy is turned off: it only credits the
+    //account with the score and rewards it already earned, which is what the policy allows at that point, and the
+    //rewards are transferred to the account itself. Returns the score released
+    pub fn release_pending_score(&mut self, account_id: AccountId) -> u64 {
+        assert!(
+            !self.human_gating || self.internal_is_verified_human(&account_id),
+            "The account has not proved it is human"
+        );
+        self.internal_release_pending_score(&account_id)
+            .map(|(released, _)| released)
+            .unwrap_or(0)
+    }
+}
+
+impl Contract {
+    //check if the account completed a quest verified with I-Am-Human itself and its proof has not expired.
+    //Accounts verified before the proof was stored are accepted
+    pub(crate) fn internal_is_verified_human(&self, account_id: &AccountId) -> bool {
+        self.verified_humans
+            .get(account_id)
+            .is_some_and(|proof| !proof.is_some_and(|proof| proof.is_expired()))
+    }
+
+    //check if the score earned by the account has to be held as pending
+    pub(crate) fn internal_is_score_gated(&self, account_id: &AccountId) -> bool {
+        self.human_gating && !self.internal_is_verified_human(account_id)
+    }
+
+    //hold the score of a quest and the score of the seasons open right now, repeatable quests add up the score of each completion
+    pub(crate) fn internal_add_pending_score(&mut self, account_id: &AccountId, quest_id: u64, category: QuestCategory, score: u64) {
+        let season_scores = self.internal_open_season_scores(quest_id, score);
+        let mut pending = self.pending_scores.get(account_id).unwrap_or_default();
+        let held = pending.entry(quest_id).or_insert(PendingScore { category, score: 0, seasons: BTreeMap::new() });
+        held.score += score;
+        for (season_id, score) in season_scores {
+            *held.seasons.entry(season_id).or_insert(0) += score;
+        }
+        self.pending_scores.insert(account_id, &pending);
+    }
+
+    //take the score held for a quest of the account (when the quest is revoked). Returns the score taken
+    pub(crate) fn internal_take_pending_score(&mut self, account_id: &AccountId, quest_id: u64) -> u64 {
+        let mut pending = match self.pending_scores.get(account_id) {
+            Some(pending) => pending,
+            None => return 0,
+        };
+        let taken = pending.remove(&quest_id).map(|held| held.score).unwrap_or(0);
+        if pending.is_empty() {
+            self.pending_scores.remove(account_id);
+        } else {
+            self.pending_scores.insert(account_id, &pending);
+        }
+        taken
+    }
+
+    //move the pending score of the account into its Proof of Reputation and into the seasons that were open when it was
+    //earned (unless they are closed now), and transfer its held rewards. Returns the score released and the updated
+    //reputation, None if there was no score to release
+    pub(crate) fn internal_release_pending_score(&mut self, account_id: &AccountId) -> Option<(u64, PoR)> {
+        self.internal_release_pending_rewards(account_id);
+        let pending = self.pending_scores.get(account_id)?;
+        let released: u64 = pending.values().map(|held| held.score).sum();
+        let por = self.internal_update_por(account_id, ReputationReason::PendingReleased, None, |por| {
+            por.score += released;
+            for held in pending.values() {
+                *por.categories.entry(held.category).or_insert(0) += held.score;
+            }
+        })?;
+        for (quest_id, held) in pending.iter() {
+            self.internal_add_to_seasons(account_id, *quest_id, &held.seasons);
+        }
+        self.pending_scores.remove(account_id);
+        Some((released, por))
+    }
+}
@@ -0,0 +1,37 @@
+use crate::*;
+
+//get the number of roles of a Sputnik/AstroDAO policy (answer of get_policy) that have the account as a member of
+//their group. Only the roles in the passed in list are counted, all of them if it is empty. None if it can't be parsed
+pub(crate) fn dao_roles_of(result: &str, account_id: &AccountId, roles: &[String]) -> Option<u128> {
+    let policy: serde_json::Value = serde_json::from_str(result).ok()?;
+    let count = policy
+        .get("roles")?
+        .as_array()?
+        .iter()
+        .filter(|role| {
+            let name = role.get("name").and_then(|name| name.as_str()).unwrap_or_default();
+            roles.is_empty() || roles.iter().any(|role_name| role_name == name)
+        })
+        .filter(|role| {
+            role.get("kind")
+                .and_then(|kind| kind.get("Group"))
+                .and_then(|group| group.as_array())
+                .is_some_and(|group| group.iter().any(|member| member.as_str() == Some(account_id.as_str())))
+        })
+        .count();
+    Some(count as u128)
+}
+
+#[near_bindgen]
+impl Contract {
+    // Hand the quest registry and the scoring (category weights) to a DAO, or take them back with None.
+    // While there is a governance account only it can make these changes, including changing the governance account.
+    pub fn set_governance_account(&mut self, governance_account: Option<AccountId>) {
+        self.assert_governance();
+        self.governance_account = governance_account;
+    }
+
+    pub fn get_governance_account(&self) -> Option<AccountId> {
+        self.governance_account.clone()
+    }
+}
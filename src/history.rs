@@ -0,0 +1,90 @@
+use crate::*;
+
+//why the reputation of an account changed
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ReputationReason {
+    //a quest was completed (or completed again), the delta is 0 when the score is held under the human gating policy
+    QuestCompleted,
+    //a moderator revoked a quest obtained by fraud
+    QuestRevoked,
+    //the score held under the human gating policy was released
+    PendingReleased,
+    Vouched,
+    //the voucher was penalized and its vouch revoked
+    VouchRevoked,
+    Penalized,
+    Bonded,
+    Unbonded,
+    //the bond was slashed when a quest was revoked
+    BondSlashed,
+}
+
+//an entry of the reputation history of an account
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationChange {
+    pub quest_id: Option<u64>,
+    //change of the score and score after the change
+    pub delta: i64,
+    pub score: u64,
+    pub reason: ReputationReason,
+    //account that made the change, the contract itself for claims verified with a cross contract call
+    pub actor: AccountId,
+    pub timestamp: U64,
+    pub block_height: U64,
+}
+
+#[near_bindgen]
+impl Contract {
+    //get the reputation history of the account, oldest changes first. The history is kept apart from the tokens
+    //so it is not lost when a token is burned
+    pub fn get_reputation_history(&self, account_id: AccountId, from: Option<U64>, limit: Option<u64>) -> Vec<ReputationChange> {
+        let history = match self.reputation_history.get(&account_id) {
+            Some(history) => history,
+            None => return vec![],
+        };
+        //where to start pagination - if we have a from index, we'll use that - otherwise start from 0 index
+        let start = from.map(u64::from).unwrap_or(0);
+        //take the first "limit" elements. If we didn't specify a limit, use 50
+        let end = std::cmp::min(history.len(), start.saturating_add(limit.unwrap_or(50)));
+        (start..end).filter_map(|index| history.get(index)).collect()
+    }
+}
+
+impl Contract {
+    //append a change to the reputation history of the account and write the checkpoint of the new score
+    pub(crate) fn internal_record_reputation_change(
+        &mut self,
+        account_id: &AccountId,
+        reason: ReputationReason,
+        quest_id: Option<u64>,
+        previous_score: u64,
+        score: u64,
+    ) {
+        let mut history = self.reputation_history.get(account_id).unwrap_or_else(|| {
+            //if the account doesn't have a history, we create a new vector
+            Vector::new(
+                StorageKey::ReputationHistoryInner {
+                    //we get a new unique prefix for the collection
+                    account_id_hash: hash_account_id(account_id),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+        history.push(&ReputationChange {
+            quest_id,
+            delta: score as i64 - previous_score as i64,
+            score,
+            reason,
+            actor: env::predecessor_account_id(),
+            timestamp: U64(env::block_timestamp()),
+            block_height: U64(env::block_height()),
+        });
+        self.reputation_history.insert(account_id, &history);
+
+        self.internal_checkpoint_score(account_id, previous_score, score);
+    }
+}
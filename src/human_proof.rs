@@ -0,0 +1,92 @@
+use crate::*;
+
+//issuer of SBTs accepted as proof of humanity and the classes of its SBTs that are accepted
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HumanProofIssuer {
+    pub issuer: AccountId,
+    pub classes: Vec<u64>,
+}
+
+//the issuers accepted when the contract is deployed: the face verification SBT of Fractal
+pub(crate) fn default_human_proof_issuers() -> Vec<HumanProofIssuer> {
+    vec![HumanProofIssuer {
+        issuer: "fractal.i-am-human.near".parse().unwrap(),
+        classes: vec![1],
+    }]
+}
+
+//the SBT that proved the humanity of an account, stored on the I'm Human quest token
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct HumanProof {
+    pub issuer: AccountId,
+    pub token_id: u64,
+    pub class: u64,
+    //unix time in milliseconds in which the SBT expires, None if it never expires
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+}
+
+impl HumanProof {
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= env::block_timestamp() / 1_000_000)
+    }
+}
+
+//metadata of an SBT as returned by the registry (NEP-393), the fields we don't use are ignored
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct SbtMetadata {
+    class: u64,
+    #[serde(default)]
+    expires_at: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct OwnedSbt {
+    token: u64,
+    metadata: SbtMetadata,
+}
+
+//parse the answer of sbt_tokens_by_owner: the SBTs of the account grouped by issuer. None if it can't be parsed
+fn parse_sbts(result: &str) -> Option<Vec<(AccountId, Vec<OwnedSbt>)>> {
+    serde_json::from_str(result).ok()
+}
+
+#[near_bindgen]
+impl Contract {
+    //change the issuers and classes of SBTs accepted as proof of humanity, only the governance account (or the owner if there is none) can call it
+    pub fn set_human_proof_issuers(&mut self, issuers: Vec<HumanProofIssuer>) {
+        self.assert_governance();
+        self.human_proof_issuers = issuers;
+    }
+}
+
+impl Contract {
+    //get the SBTs of the registry answer that are valid proofs of humanity: issued by an accepted issuer,
+    //of an accepted class and not expired. None if the answer can't be parsed
+    pub(crate) fn internal_human_proofs(&self, result: &str) -> Option<Vec<HumanProof>> {
+        let proofs = parse_sbts(result)?
+            .into_iter()
+            .filter_map(|(issuer, sbts)| {
+                let accepted = self.human_proof_issuers.iter().find(|accepted| accepted.issuer == issuer)?;
+                Some(
+                    sbts.into_iter()
+                        .filter(|sbt| accepted.classes.contains(&sbt.metadata.class))
+                        .map(|sbt| HumanProof {
+                            issuer: issuer.clone(),
+                            token_id: sbt.token,
+                            class: sbt.metadata.class,
+                            expires_at: sbt.metadata.expires_at,
+                        })
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .flatten()
+            .filter(|proof| !proof.is_expired())
+            .collect();
+        Some(proofs)
+    }
+}
@@ -1,5 +1,6 @@
 use crate::*;
 use near_sdk::{CryptoHash};
+use near_sdk::serde::de::DeserializeOwned;
 use std::mem::size_of;
 
 //convert the royalty percentage and amount to pay into a payout (U128)
@@ -45,6 +46,25 @@ pub(crate) fn hash_account_id(account_id: &AccountId) -> CryptoHash {
     hash
 }
 
+//used to generate a unique prefix for the set of tokens of a quest
+pub(crate) fn hash_quest_id(quest_id: u64) -> CryptoHash {
+    //get the default hash
+    let mut hash = CryptoHash::default();
+    //we hash the quest ID and return it
+    hash.copy_from_slice(&env::sha256(&quest_id.to_le_bytes()));
+    hash
+}
+
+//serialize the passed in value into the JSON string (with single quotes) stored in the token extra field
+pub(crate) fn to_extra<T: Serialize>(value: &T) -> String {
+    str::replace(&serde_json::to_string(value).unwrap(), "\"", "'")
+}
+
+//parse a value back from the JSON string (with single quotes) stored in the token extra field
+pub(crate) fn from_extra<T: DeserializeOwned>(extra: &str) -> T {
+    serde_json::from_str(&str::replace(extra, "'", "\"")).unwrap()
+}
+
 //used to make sure the user attached exactly 1 yoctoNEAR
 pub(crate) fn assert_one_yocto() {
     assert_eq!(
@@ -54,6 +74,15 @@ pub(crate) fn assert_one_yocto() {
     )
 }
 
+//used to make sure that a callback is called by the contract itself
+pub(crate) fn assert_self() {
+    assert_eq!(
+        env::predecessor_account_id(),
+        env::current_account_id(),
+        "Method is private",
+    )
+}
+
 //Assert that the user has attached at least 1 yoctoNEAR (for security reasons and to pay for storage)
 pub(crate) fn assert_at_least_one_yocto() {
     assert!(
@@ -86,6 +115,40 @@ pub(crate) fn refund_deposit(storage_used: u64) {
 }
 
 impl Contract {
+    //make sure that the caller is the owner of the contract
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+
+    //quest registry and scoring changes are made by the governance account if there is one, by the owner otherwise
+    pub(crate) fn assert_governance(&self) {
+        match &self.governance_account {
+            Some(governance_account) => assert_eq!(
+                &env::predecessor_account_id(),
+                governance_account,
+                "Only the governance account can call this method"
+            ),
+            None => self.assert_owner(),
+        }
+    }
+
+    //get the first token owned by the account that has the passed in title
+    pub(crate) fn internal_token_with_title(&self, account_id: &AccountId, title: &str) -> Option<TokenId> {
+        let tokens = self.tokens_per_owner.get(account_id)?;
+        let token_id = tokens.iter().find(|token_id| {
+            self.token_metadata_by_id
+                .get(token_id)
+                .and_then(|metadata| metadata.title)
+                .as_deref()
+                == Some(title)
+        });
+        token_id
+    }
+
     //add a token to the set of tokens an owner has
     pub(crate) fn internal_add_token_to_owner(
         &mut self,
@@ -98,7 +161,7 @@ impl Contract {
             UnorderedSet::new(
                 StorageKey::TokenPerOwnerInner {
                     //we get a new unique prefix for the collection
-                    account_id_hash: hash_account_id(&account_id),
+                    account_id_hash: hash_account_id(account_id),
                 }
                 .try_to_vec()
                 .unwrap(),
@@ -110,6 +173,29 @@ impl Contract {
 
         //we insert that set for the given account ID. 
         self.tokens_per_owner.insert(account_id, &tokens_set);
+        self.sorted_tokens_per_owner.insert(&owner_sort_key(account_id, token_id), &());
+    }
+
+    //add a token to the set of tokens of a quest, returns false if it was already in it
+    pub(crate) fn internal_add_token_to_quest(&mut self, quest_id: u64, token_id: &TokenId) -> bool {
+        //get the set of tokens for the given quest
+        let mut tokens_set = self.tokens_per_type.get(&quest_id).unwrap_or_else(|| {
+            //if the quest doesn't have any tokens, we create a new unordered set
+            UnorderedSet::new(
+                StorageKey::TokensPerTypeInner {
+                    //we get a new unique prefix for the collection
+                    token_type_hash: hash_quest_id(quest_id),
+                }
+                .try_to_vec()
+                .unwrap(),
+            )
+        });
+
+        //we insert the token ID into the set and the set for the given quest ID
+        let inserted = tokens_set.insert(token_id);
+        self.tokens_per_type.insert(&quest_id, &tokens_set);
+        self.sorted_tokens_per_quest.insert(&quest_sort_key(quest_id, token_id), &());
+        inserted
     }
 
     //remove a token from an owner (internal method and can't be called directly via CLI).
@@ -127,6 +213,7 @@ impl Contract {
 
         //we remove the the token_id from the set of tokens
         tokens_set.remove(token_id);
+        self.sorted_tokens_per_owner.remove(&owner_sort_key(account_id, token_id));
 
         //if the token set is now empty, we remove the owner from the tokens_per_owner collection
         if tokens_set.is_empty() {
@@ -137,6 +224,86 @@ impl Contract {
         }
     }
 
+    //burns a token: it is removed from its owner, its quest and the token collections (internal method and can't be called directly via CLI).
+    pub(crate) fn internal_burn(&mut self, token_id: &TokenId, authorized_id: &AccountId, memo: Option<String>) {
+        //the backfill of the migrated tokens walks the token IDs by position, burning would move them
+        assert!(self.legacy_token_backfill.is_none(), "Tokens can't be burned until migrate_tokens is done");
+        let token = self.tokens_by_id.remove(token_id).expect("No token");
+        self.internal_remove_token_from_owner(&token.owner_id, token_id);
+        if let Some(quest_id) = self.internal_quest_id_of(token_id) {
+            if let Some(mut tokens_set) = self.tokens_per_type.get(&quest_id) {
+                tokens_set.remove(token_id);
+                self.tokens_per_type.insert(&quest_id, &tokens_set);
+            }
+            self.sorted_tokens_per_quest.remove(&quest_sort_key(quest_id, token_id));
+        }
+        self.token_metadata_by_id.remove(token_id);
+        self.sorted_token_ids.remove(&token_sort_key(token_id));
+
+        // Construct the burn log as per the events standard.
+        let nft_burn_log: EventLog = EventLog {
+            standard: NFT_STANDARD_NAME.to_string(),
+            version: NFT_METADATA_SPEC.to_string(),
+            event: EventLogVariant::NftBurn(vec![NftBurnLog {
+                owner_id: token.owner_id.to_string(),
+                authorized_id: Some(authorized_id.to_string()),
+                token_ids: vec![token_id.to_string()],
+                memo,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_burn_log.to_string());
+    }
+
+    //mints a new token with the passed in metadata for the owner_id (internal method and can't be called directly via CLI).
+    pub(crate) fn internal_mint(
+        &mut self,
+        token_id: &TokenId,
+        metadata: TokenMetadata,
+        owner_id: &AccountId,
+        royalty: HashMap<AccountId, u32>,
+    ) {
+        //we create the token struct with no approved account IDs
+        let token = Token {
+            owner_id: owner_id.clone(),
+            approved_account_ids: Default::default(),
+            next_approval_id: 0,
+            royalty,
+        };
+
+        //we insert the token ID and token struct and make sure that the token doesn't exist
+        assert!(
+            self.tokens_by_id.insert(token_id, &token).is_none(),
+            "Token already exists"
+        );
+
+        //we insert the token ID and metadata and add the token to the owner
+        self.token_metadata_by_id.insert(token_id, &metadata);
+        self.sorted_token_ids.insert(&token_sort_key(token_id), &());
+        self.internal_add_token_to_owner(owner_id, token_id);
+
+        // Construct the mint log as per the events standard.
+        let nft_mint_log: EventLog = EventLog {
+            // Standard name ("nep171").
+            standard: NFT_STANDARD_NAME.to_string(),
+            // Version of the standard ("nft-1.0.0").
+            version: NFT_METADATA_SPEC.to_string(),
+            // The data related with the event stored in a vector.
+            event: EventLogVariant::NftMint(vec![NftMintLog {
+                // Owner of the token.
+                owner_id: owner_id.to_string(),
+                // Vector of token IDs that were minted.
+                token_ids: vec![token_id.to_string()],
+                // An optional memo to include.
+                memo: None,
+            }]),
+        };
+
+        // Log the serialized json.
+        env::log_str(&nft_mint_log.to_string());
+    }
+
     //transfers the NFT to the receiver_id (internal method and can't be called directly via CLI).
     pub(crate) fn internal_transfer(
         &mut self,
@@ -150,6 +317,11 @@ impl Contract {
         //get the token object by passing in the token_id
         let token = self.tokens_by_id.get(token_id).expect("No token");
 
+        //the Proof of Reputation stays with the account that earned it: the boosts of its bonds and vouches are taken back from it
+        //and its score checkpoints are kept by account
+        let title = self.token_metadata_by_id.get(token_id).and_then(|metadata| metadata.title);
+        assert!(title.as_deref() != Some(POR_TITLE), "The Proof of Reputation can't be transferred");
+
         //if the sender doesn't equal the owner, we check if the sender is in the approval list
 		if sender_id != &token.owner_id {
 			//if the token's approved account IDs doesn't contain the sender, we panic
@@ -0,0 +1,143 @@
+use crate::*;
+
+//an account trusted to award off-chain quests
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Issuer {
+    //quests the issuer is allowed to award
+    pub quest_ids: Vec<u64>,
+    //maximum number of quests the issuer can award
+    pub quota: u64,
+    //number of quests awarded so far
+    pub awarded: u64,
+}
+
+//the issuer as returned from the view calls
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonIssuer {
+    pub account_id: AccountId,
+    #[serde(flatten)]
+    pub issuer: Issuer,
+}
+
+#[near_bindgen]
+impl Contract {
+    //whitelist an account to award the passed in quests, only the governance account (or the owner if there is none) can call it
+    pub fn add_issuer(&mut self, account_id: AccountId, quest_ids: Vec<u64>, quota: u64) {
+        self.assert_governance();
+        for quest_id in quest_ids.iter() {
+            let quest = self.quests.get(quest_id).expect("Quest not found");
+            assert_eq!(
+                quest.verifier,
+                QuestVerifier::Issuer,
+                "Quest {} can't be awarded by issuers",
+                quest_id
+            );
+        }
+
+        //if the issuer already existed we keep the count of quests it has awarded
+        let awarded = self.issuers.get(&account_id).map(|issuer| issuer.awarded).unwrap_or(0);
+        self.issuers.insert(&account_id, &Issuer { quest_ids, quota, awarded });
+    }
+
+    //change the maximum number of quests an issuer can award, only the governance account (or the owner if there is none) can call it
+    pub fn set_issuer_quota(&mut self, account_id: AccountId, quota: u64) {
+        self.assert_governance();
+        let mut issuer = self.issuers.get(&account_id).expect("Issuer not found");
+        issuer.quota = quota;
+        self.issuers.insert(&account_id, &issuer);
+    }
+
+    //remove an account from the issuers, only the governance account (or the owner if there is none) can call it
+    pub fn remove_issuer(&mut self, account_id: AccountId) {
+        self.assert_governance();
+        assert!(self.issuers.remove(&account_id).is_some(), "Issuer not found");
+    }
+
+    //get the information of an issuer
+    pub fn get_issuer(&self, account_id: AccountId) -> Option<JsonIssuer> {
+        self.issuers
+            .get(&account_id)
+            .map(|issuer| JsonIssuer { account_id, issuer })
+    }
+
+    //get the issuers using pagination
+    pub fn get_issuers(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonIssuer> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.issuers.iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(account_id, issuer)| JsonIssuer { account_id, issuer })
+            .collect()
+    }
+
+    // Method for issuers to award an off-chain quest (course, workshop...) to an account.
+    // The attached deposit pays the storage of the mint in the storage balance of the account and the part
+    // that is not used is given back to the issuer. The quest is minted the same way as the quests verified
+    // with a cross contract call. If the quest can't be awarded the deposit is given back and the typed error is returned.
+    #[payable]
+    pub fn award_quest(&mut self, account_id: AccountId, quest_id: u64, evidence_uri: Option<String>) -> ClaimOutcome {
+        self.assert_tokens_migrated();
+        let issuer_id = env::predecessor_account_id();
+        let deposit = env::attached_deposit();
+        let (mut issuer, quest) = match self.internal_awardable_quest(&issuer_id, &account_id, quest_id) {
+            Ok(awardable) => awardable,
+            Err(error) => {
+                if deposit > 0 {
+                    Promise::new(issuer_id).transfer(deposit);
+                }
+                return self.internal_log_claim_failed(&account_id, quest_id, error);
+            }
+        };
+
+        // Count the award against the quota of the issuer
+        issuer.awarded += 1;
+        self.issuers.insert(&issuer_id, &issuer);
+
+        // Keep the deposit as storage balance of the account, the storage of the mint is charged against it
+        let available_before = self.internal_storage_available(&account_id);
+        if deposit > 0 {
+            self.internal_storage_deposit(&account_id, deposit);
+        }
+
+        let badge = QuestBadge {
+            issuer: Some(issuer_id.clone()),
+            evidence_uri,
+            ..QuestBadge::from_quest(&quest)
+        };
+        let outcome = self.internal_mint_quest(&account_id, quest_id, badge, None);
+
+        // The account can't withdraw what is left of the deposit of the issuer, it goes back to the issuer
+        let unused = std::cmp::min(
+            deposit,
+            self.internal_storage_available(&account_id).saturating_sub(available_before),
+        );
+        self.internal_refund_storage_deposit(&account_id, unused, &issuer_id);
+        outcome
+    }
+}
+
+impl Contract {
+    //get the issuer and a quest it can award to the account right now, or the typed reason why it can't
+    fn internal_awardable_quest(
+        &self,
+        issuer_id: &AccountId,
+        account_id: &AccountId,
+        quest_id: u64,
+    ) -> Result<(Issuer, QuestConfig), ClaimError> {
+        let issuer = self.issuers.get(issuer_id).ok_or(ClaimError::NotIssuer)?;
+        if !issuer.quest_ids.contains(&quest_id) {
+            return Err(ClaimError::IssuerNotAllowed);
+        }
+        if issuer.awarded >= issuer.quota {
+            return Err(ClaimError::IssuerQuotaReached);
+        }
+        let quest = self.internal_claimable_quest(account_id, quest_id)?;
+        Ok((issuer, quest))
+    }
+}
@@ -1,42 +1,88 @@
-use std::collections::HashMap;
+//the functions generated by ext_contract take the receiver, deposit and GAS on top of the method arguments
+#![allow(clippy::too_many_arguments)]
+
+use std::collections::{BTreeMap, HashMap};
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, TreeMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
-    env, log, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, PromiseOrValue, Gas
+    env, near_bindgen, AccountId, Balance, CryptoHash, PanicOnDefault, Promise, PromiseOrValue, PublicKey
 };
-type U128String = U128;
 
 use crate::internal::*;
 pub use crate::metadata::*;
 pub use crate::mint::*;
 pub use crate::nft_core::*;
-pub use crate::royalty::*;
+pub use crate::storage::*;
+pub use crate::reputation::*;
+pub use crate::quests::*;
+pub use crate::issuers::*;
+pub use crate::oracles::*;
+pub use crate::seasons::*;
+pub use crate::eligibility::*;
+pub use crate::pending_claims::*;
+pub use crate::vouches::*;
+pub use crate::bonds::*;
+pub use crate::config::*;
+pub use crate::gating::*;
+pub use crate::human_proof::*;
+pub use crate::checkpoints::*;
+pub use crate::history::*;
+pub use crate::snapshots::*;
+pub use crate::rewards::*;
+pub use crate::enumeration::*;
+pub use crate::token_ids::*;
 pub use crate::events::*;
+use crate::migrate::*;
 
 mod internal;
+pub mod approval;
 mod enumeration; 
+mod token_ids;
 mod metadata; 
 mod mint; 
 mod nft_core; 
-mod royalty; 
+pub mod royalty; 
+mod reputation;
+mod quests;
+mod issuers;
+mod oracles;
+mod seasons;
+mod eligibility;
+mod pending_claims;
+mod vouches;
+mod bonds;
+mod moderation;
+mod gating;
+mod config;
+mod human_proof;
+mod governance;
+mod checkpoints;
+mod history;
+mod snapshots;
+mod rewards;
+mod storage;
 mod events;
+mod migrate;
 
 
 pub const NFT_METADATA_SPEC: &str = "1.0.0";
 pub const NFT_STANDARD_NAME: &str = "nep171";
+//standard name and version of the events specific to the Proof of Reputation contract
+pub const POR_STANDARD_NAME: &str = "por";
+pub const POR_EVENT_VERSION: &str = "1.0.0";
 pub const ICON: &str = "data:image/jpeg;base64,/9j/4AAQSkZJRgABAQAAAQABAAD/4gHYSUNDX1BST0ZJTEUAAQEAAAHIAAAAAAQwAABtbnRyUkdCIFhZWiAH4AABAAEAAAAAAABhY3NwAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAA9tYAAQAAAADTLQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAlkZXNjAAAA8AAAACRyWFlaAAABFAAAABRnWFlaAAABKAAAABRiWFlaAAABPAAAABR3dHB0AAABUAAAABRyVFJDAAABZAAAAChnVFJDAAABZAAAAChiVFJDAAABZAAAAChjcHJ0AAABjAAAADxtbHVjAAAAAAAAAAEAAAAMZW5VUwAAAAgAAAAcAHMAUgBHAEJYWVogAAAAAAAAb6IAADj1AAADkFhZWiAAAAAAAABimQAAt4UAABjaWFlaIAAAAAAAACSgAAAPhAAAts9YWVogAAAAAAAA9tYAAQAAAADTLXBhcmEAAAAAAAQAAAACZmYAAPKnAAANWQAAE9AAAApbAAAAAAAAAABtbHVjAAAAAAAAAAEAAAAMZW5VUwAAACAAAAAcAEcAbwBvAGcAbABlACAASQBuAGMALgAgADIAMAAxADb/2wBDAAMCAgICAgMCAgIDAwMDBAYEBAQEBAgGBgUGCQgKCgkICQkKDA8MCgsOCwkJDRENDg8QEBEQCgwSExIQEw8QEBD/2wBDAQMDAwQDBAgEBAgQCwkLEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBAQEBD/wAARCABgAGADASIAAhEBAxEB/8QAHQAAAQQDAQEAAAAAAAAAAAAACAUGBwkAAQQDAv/EADsQAAIBAgMGBAQFAgQHAAAAAAECAwQFAAYRBxIhMUFREyJhcQgUgbEyQlKRoSNiGDNywjSCosPR4fD/xAAbAQADAQADAQAAAAAAAAAAAAAGBwgFAAIEA//EADURAAECAwUFBgUFAQEAAAAAAAECAwQFEQAGITFBBxJRYXEUIjKBkbETUnKhwRUjYtHhkoL/2gAMAwEAAhEDEQA/ALU8ZjMfEsscMbzTSLHHGpZ2Y6BQOJJPQY5lbgFcBb6ZlRS7sFVRqSToAMQ3tC+JfKuVppbXlqAXyvj1VpEfdpo27b/5yP7eHTe15Rftt281ubqioyxlSpkp7EhMcsy+V60g8Seoj7DrzPPQQthdTy+SkrMPLtM1Z/8AP9ny42ct1tm6HG0xc5BxxDeX/Rz8hSmp0tIOY9vO1DMcrtJmSW3wueEFvHgKg7Bh5z9WJwy628Xa6S+LcrnVVbsdS00zOT9ScKmXchZpzOomtlsf5fXT5iU+HH9CfxfTXD1otgV0kUGvzHSQN2igaXT6krhXTK87CFkRsTVXAkqPoK0sw+0yKR/tI3GyNEjHz3QT62Ha23q8WiQTWm61lFIOIannaMg+6kYkLKnxJbYspzRtDm2e5QIRvU9zHzKOOxZvOPowPrj2q9gNyjTWgzJSzsBwE1O0Wv1BbDMzFkHNOWFM1ztjGmB0+YhPiRj3I/D9dMMOU32kk1cDcBFpKjkMUk9AoAnyFitb0gvB+y6G3K6KSK+W8AfSxgbLvi6ybnKohs2b6YZcuUuipK8m9SSt23zxjJ6BuHTeJ01npHWRQ6MGVhqCDqCO+Ko8T/8AD18SdwyLVU2UM6VUlXluQiKGZvNJbyTwIPNou69OY5bpO4WYknce9f7sqL57Jm22lR0gBwxLZNaj+BONf4mtdDkCb+Mx8QzQ1EKVFPKkkUqh0dCCrKRqCCOYIx942LIQgg0NswPnxQ7TJKCmj2eWeZllq0E1xkVtN2I/gi/5tNT6bvPU4n2rqoaGkmral92KnjaWRuyqNSf2GK/s3ZjrM25muWZK4/1a+oeXd113F10VB6KoCj0AwH3ymaoKDEO2aKcqP/Iz9cB0rZjbNpGiZTFUY8KoZoRzUfD6UJ60skYmDZrsogeCHMOaaffLgSU1G44AdHkHXuF5d+wa+yXKaZkzH8zWxB6G2gTSqw1Dvr5E/cEn0XTrghsTVeyeuQx7DDGiqd4jMVyA9zypZj3un7kMewQpoojvEZiuQHDieVLaVVRFjRQqqNFUDQAdhjY/EPfGYb+YrmQwt8D6aHWUg/8AThaOOBsbxsumGVRC9wWbw5YxlV1KOoZWGhBGoI7Y4bfUn/h3P+n/AMY78fWfSSIu9HKg38aYg5BQ0I/PAgixYtBbVQ2iPaXsogEE2YcrU/htGC9TRIOBHV4x0PUry7djDuC+5cRgetreU0y3mP5uiiCUVyBmjVRoEkB86e2pBHo2nTDs2WX3fmC/0SYr3lgVbUcyBmknUgYg50BByFmPdKfORKuwRJqad0nM0zB9xyrYhPg62uy3Kll2WX2oZ5qKM1FqldtS0IPnh4/p13l9Cw4bo1KHFXuSM1V2SM3WnNdtbSe2VST7uugdAdHQ+jKWU+hOLO6Gtp7jQ09wpH34KqJJo27owBB/Y4o6WvlxvcVmPaya2tXdRKZqmPhxRD9SRwWPF61B61s09slzNo2X5jrFOhNE0APYykR/78Apg2/iAgkqdkOYY4xqQlO/0WojY/wDgJMLy/alGObTpufk/wBCxHspQkSp5YzLhHkEpp7m0+bELctLk964qN+tqpG1/tXRR/IbD+ndooJJETeZFLBe5A5YZ2xyZJcgUKKeMMs6N7+IzfZhh64lqeKUuZPlXzEehoPtYeni1Lmb5X859AaD7WZtRe7lUag1LID0Ty/+8cWpLak6knnhYzFbY6aRauHQLM2jL2buMIw5j3wLOBQVRRtpQxbW2FNigNkLlxGPaOsqY+AkJHZuOPDHZb6cSMZX0IQ6AdzipryvS6EgHIqZNhaE6EA1JwAFciTrpnbacKQmqhZQRiyKzDQkAkdsR/tvty1WTlrt3z0VVG2v9raqR+5X9sSFhl7YZki2f3BGPGaSnjX38VW+ynE/3PfUi8kG4yKVdSKcApVCPQm3eRrUiZsFPzgeRND9rDrix7YDeGvmxvKdezalaBaYnXXUws0X/bxXDiwz4YaeWm2FZWjlXRjHVSAejVUrD+CMW/KyfikcvyLe7bS2gyZhw+IOgDoULr7Cz5zlZzmDKV4sirq9bQzQoNPzFCF/nTFfjqyOyMCCp0IPQ4scwF3xB5JGT9oNVPSxBKG8618AHJWYnxF9NH1IHQEYHb9QKnGm4xA8OB6HI+uHmLAmyqaoZfelzhxXRSeozHWmPQGypsGvkfh3DLksgEm8KuFT+YaBX09tF/8AhiXcChY7zW5futNeKBgJqZ94A8mHIqfQjUfXBM5ZzLbc12mK7W2QbreWSInVon6o3r9xocTHfCVLh4ntqB3F58lf7n1rbcvlKlw8V21A7i8+Sv8Ac+tbeOatfloBpw3z9sINLR1VW4Wngd+PMDh+/LD4kjjlXcljV1PRlBH842iquiqoAHIAaDAIuH+IveJsNsR5Ya+GlONolkili4SIV98d1s/y5P8AUPtjs0BGhGoxiqqjRVCjsBpg2nt/1T6VKgH2AlaiDUHDA1yOOnE2J1v76d0i28RHt5vkYht+XI5AXLGsmUflABVNffV/2xI+ZcyW3Ktqlu1zk0VPLHGCA0r9EX1+w1OBlvt5rcw3apvFewM1S+8QOSjkFHoAAPpjY2TXZdj5kJu6mjTNaH5lkUoPpBqedLFdz5UuIiu2rHcRlzV/mfWluJFaR1jQEsxAAHU4s82f2Fsr5HsGXXXde326np5Bpp51QBj772uAd+GDZ8ue9qNFNWRB7dYtLlUhuTsjDwk06gvukjqFYYsCxWUraISXDrlYK20Tlt+JYlTZqW6qVyKvCOtKnooWaO0TaJasgWvx5ys9wnBFLSg8XP6m7KOp+gwJmbrlcc6VtRdL1VGarmO8HPJNOSqOijlphwZ/pM5nMVZW5wpKpaqSVhvsjeEQOQjPIqBy06YbGHZLLswH6etiIAcDqaKOhB0HAc8648KRhM72zJEzbioVRaLKgpAyII1I1J4HCmGNTVmTQyQSNDKu668xhTy3me8ZUuAuFnqTGx4SRtxjlX9LDr9x00xx5jvVNJULT0yLIYj55P8AaMcMNRFMNVbj1B54mG/ezOOu0pa9wuwislUrQcFjQjj4TpQ4Cztn+1GUX9hEwkZutxRFFNqyVzRXxA57viTrUDeJA5b2zZZu0axXgta6rkd/Vom9Qw5exA9zh7UV3tNxUSUF0pKle8U6t9jgTMbABI1AOERF3KhHVFTCyjlmPwfvYmi7jwjyiqHWUcvEPLI/e051l1tduQyXC5UtMo5maZU+5wycybZcsWiNorQzXSq5AR6rEp7s55+wB+mIAAAHAAY3gtlmxqVwrgXHPKepoBuA9aEn0ULFsJciEaUFRDhXy8I88SfuLK+Zc03nNdebhd6nfI1EcajSOJeyjp78z1wlRxvK4jjXVm5DCjZsu3S+SBaOAiL80z8EUe/X2GHZVZJFpolnoXaeRR/X1HE+qjt6Yom6NyHJiENtI+FDJwqBQU4JGvXIa44EO2kbXpHs6hTAwhS5GUolpOSDoXKeEDPd8StKAlQ5cmZgvOQ7tS3zLtc1NXUzb3iLyfurD8ykcCDg69kW12x7VbJ8xTFKW7UqgV1CW4of1p1ZCeR6cj6gLh57LKLaIM20Fx2fUFa9dDMqiSONjCATxEraaBCDx14aYdM5u3BPwKW26NlsUSdKDQ8uedceNYcgr2TJ+aORkYouqeUVL4knUDSmQAwpQYUFLAZ4IKmJoKmGOWNxoyOoZSOxBxCm3nZLYVyFeMxZUtS0NxooxUOKYlUaFSPE8n4V0TU8APw4m/HlV0sFbSzUVVEskNRG0UiMNQysNCD7g4V0umD0ufS60ogAgkVwI4GzDmMuYmTKmnkgkg0JGIPEWrMwq5WyvfM43ymy/l6iepraltFVeAUdWY9FHMnC9XbLM2naJVbPrdaKmauSqeOMFCAYd47sxPIIV0O9y0wY+yLZFZNlVk+Wp9yqu1UoNdXFeLn9CdkB5DrzPo2J3eKHlsMFIopax3RyOp5e+XEhWyWQRExiCF1ShB7x5jQc/bPhVo2f4VsiwZcprfeqquqLoq61FdBNuaueiqQRujkNRqeZ7YSa74Q7PJIWtmdKunTXgJ6NZj+4dPtggsZhBRsilse4p55lO8o1NBu5/TS1BS+9k7ljSWYeJVupFBvHfwH171hdtvwL2WGQG77Q62qjB4rT29YCR7s7/bDt/wAH2y2ktEtPb462W46aw1dbP4gDDoyKApB68MTrjMaDEO1DrDiEioNccfetvTM773hm7KmImLXuqFDukIwP0BNgmvmXbllW5S2S60fy09Od3cA8pXoy91PQ44MF1tF2d2vP9q8CcLBcIFJpaoLxQ/pbup6j6jA2UuQMwjOcGT623TR1TTqr6LqBFvcZAeRXTjryw5pNeBiYw5UuiVoHeHIajl7Wmme3biZZEgIqtCz3TrU6Hn758QJS2S7BshNYaHNeYstxVtxrgakJUEtCiMfJ/T13Dquh4g88TVS0tLRQJS0VNFTwxgKkcSBFUdgBwGNwQRUsEdNAgSOJAiKBoFUDQAfTHphXTCYvzF5TrqiQSaAnADgLN2XS5mWsJZaSAQACaYk8Tb//2Q==";
 
-const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
-const GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
-const MIN_GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas(100_000_000_000_000);
 const NO_DEPOSIT: Balance = 0;
 
 #[derive(Serialize, Deserialize, BorshDeserialize, BorshSerialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PoR {
     score : u64,
+    //score earned in each category, reputations created before the categories only have the total score
+    #[serde(default)]
+    categories : BTreeMap<QuestCategory, u64>,
     quest_completed : u64,
     date_last_quest : u64
 }
@@ -45,35 +91,21 @@ pub struct PoR {
 #[serde(crate = "near_sdk::serde")]
 pub struct Quest {
     score : u64,
-    date : u64
-}
-
-enum QuestType {
-    Quest0,
-    Quest1,
-    Quest2,
-    Quest3,
-}
-
-impl QuestType {
-    fn from_number(number: u64) -> Option<QuestType> {
-        match number {
-            0 => Some(QuestType::Quest0),
-            1 => Some(QuestType::Quest1),
-            2 => Some(QuestType::Quest2),
-            3 => Some(QuestType::Quest3),
-            _ => None,
-        }
-    }
-
-    fn get_quest_info(&self) -> (u64, &str, &str, u64, &str) {
-        match self {
-            QuestType::Quest0 => (0, "I'm Human", "Identify yourself as a human in the I am human app.", 40, "QmSt8ngyrTE6JG5gwPRNsHK3VkVMv5MZf4z2d8usGoCgNg"),
-            QuestType::Quest1 => (1, "stNEAR", "Stake NEAR in metapool to get stNEAR.", 15, "QmPKTBiKzuFg4G62hDtJuhuqwrrT2MbgWSLWX9LMxeTWQn"),
-            QuestType::Quest2 => (2, "Meta Token", "Get META token in your account.", 15, "Qmd8kZkFhFRimwSEuHa6Qyi91E1RxCU65jdunDmEYFNYcY"),
-            QuestType::Quest3 => (3, "Voting Power", "You are registered in I am human", 30, "QmZ17TnjJZEGDn1ZpjTr7cMivdxBAPWFuJhpTsm1sZr3LE",),
-        }
-    }
+    date : u64,
+    //tokens minted before the quest id was stored are matched by their title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    quest_id : Option<u64>,
+    //issuer that awarded the quest, for quests awarded with award_quest
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    issuer : Option<AccountId>,
+    //number of times a repeatable quest has been completed and the current streak
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    times_completed : Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    streak : Option<u64>,
+    //SBT that proved the humanity of the account, for quests verified with I-Am-Human
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    human_proof : Option<HumanProof>
 }
 
 #[derive(BorshSerialize)]
@@ -87,6 +119,43 @@ pub enum StorageKey {
     TokensPerTypeInner { token_type_hash: CryptoHash },
     TokenTypesLocked,
     TokensToMintCounter,
+    StorageAccounts,
+    Quests,
+    Issuers,
+    OracleKeys,
+    UsedNonces,
+    CategoryWeights,
+    Seasons,
+    SeasonScores,
+    SeasonScoresInner { season_id: u64 },
+    SeasonArchives,
+    QuestCompletions,
+    PendingClaims,
+    VouchesBy,
+    VouchesFor,
+    Bonds,
+    Unbondings,
+    Moderators,
+    PendingScores,
+    ScoreCheckpoints,
+    ScoreCheckpointsInner { account_id_hash: CryptoHash },
+    ReputationHistory,
+    ReputationHistoryInner { account_id_hash: CryptoHash },
+    Snapshots,
+    SnapshotLeaves { snapshot_id: u64 },
+    SnapshotNodes { snapshot_id: u64 },
+    RewardPools,
+    QuestRewardsPaid,
+    UnclaimedRewards,
+    SeasonArchiveSorted { season_id: u64 },
+    SeasonArchiveRanking { season_id: u64 },
+    SeasonsByQuest,
+    SortedTokenIds,
+    VerifiedHumans,
+    PendingRewards,
+    CompletedQuests,
+    SortedTokensPerOwner,
+    SortedTokensPerQuest,
 }
 
 #[near_bindgen]
@@ -97,6 +166,89 @@ pub struct Contract {
     pub tokens_by_id: LookupMap<TokenId, Token>,
     pub token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
     pub metadata: LazyOption<NFTContractMetadata>,
+    //keeps track of the NEP-145 storage balance deposited by each account
+    pub storage_accounts: LookupMap<AccountId, StorageAccount>,
+    //registry of the quests that can be completed
+    pub quests: UnorderedMap<u64, QuestConfig>,
+    //accounts trusted to award off-chain quests
+    pub issuers: UnorderedMap<AccountId, Issuer>,
+    //public keys of the oracles allowed to sign quest claims
+    pub oracle_keys: UnorderedSet<PublicKey>,
+    //nonces of the signed claims already used by each account
+    pub used_nonces: LookupSet<(AccountId, u64)>,
+    //weight of each category in the composite score, in basis points
+    pub category_weights: UnorderedMap<QuestCategory, u32>,
+    //reputation campaigns with their own quests
+    pub seasons: UnorderedMap<u64, Season>,
+    //score of each account in each season
+    pub season_scores: LookupMap<u64, UnorderedMap<AccountId, u64>>,
+    //frozen final ranking of the closed seasons
+    pub season_archives: LookupMap<u64, SeasonArchive>,
+    //ids of the seasons that include each quest
+    pub seasons_by_quest: LookupMap<u64, Vec<u64>>,
+    //number of accounts that have completed each quest
+    pub quest_completions: LookupMap<u64, u64>,
+    //tokens of each quest, the token IDs don't change when they are transferred
+    pub tokens_per_type: LookupMap<u64, UnorderedSet<TokenId>>,
+    //next number used for the token IDs, it never goes back so IDs are not reused
+    pub tokens_to_mint_counter: LazyOption<u64>,
+    //every token ID sorted by length and then value (so numeric IDs are sorted by their value), for the cursors
+    pub sorted_token_ids: TreeMap<(u64, TokenId), ()>,
+    //token IDs of each owner and of each quest in the same order, so their pages start from the cursor
+    pub sorted_tokens_per_owner: TreeMap<(AccountId, u64, TokenId), ()>,
+    pub sorted_tokens_per_quest: TreeMap<(u64, u64, TokenId), ()>,
+    //index of the next token minted before the migration to add to the token indexes, None when they are complete
+    pub legacy_token_backfill: Option<u64>,
+    //how the IDs of the quest tokens are built
+    pub token_id_scheme: TokenIdScheme,
+    //claims waiting for their verification call, by account
+    pub pending_claims: LookupMap<AccountId, Vec<PendingClaim>>,
+    //nonce of the next pending claim, so a callback only clears the claim that started it
+    pub next_claim_nonce: u64,
+    //rules of the vouches between accounts
+    pub vouch_config: VouchConfig,
+    //vouch graph: vouches made by each account and vouches received by each account
+    pub vouches_by: LookupMap<AccountId, Vec<Vouch>>,
+    pub vouches_for: LookupMap<AccountId, Vec<Vouch>>,
+    //rules of the reputation bonds
+    pub bond_config: BondConfig,
+    //NEAR locked by each account to boost its reputation
+    pub bonds: LookupMap<AccountId, Bond>,
+    //unbonded amounts waiting for the unbonding delay, by account
+    pub unbondings: LookupMap<AccountId, Vec<Unbonding>>,
+    //accounts allowed to revoke fraudulent quests
+    pub moderators: UnorderedSet<AccountId>,
+    //account that receives the slashed bonds
+    pub treasury_id: AccountId,
+    //if the score of accounts without an I-Am-Human proof is held as pending
+    pub human_gating: bool,
+    //score of each quest held until the account proves it is human
+    pub pending_scores: LookupMap<AccountId, BTreeMap<u64, PendingScore>>,
+    //issuers and classes of SBTs accepted as proof of humanity
+    pub human_proof_issuers: Vec<HumanProofIssuer>,
+    //accounts that completed a quest verified with I-Am-Human and the SBT that proved it (None for tokens minted
+    //before the proof was stored). It is kept by account so transferring the badge doesn't transfer the humanity
+    pub verified_humans: LookupMap<AccountId, Option<HumanProof>>,
+    //quests completed by each account and their badges. It is kept by account so transferring a badge doesn't
+    //transfer the completion, and the account can't claim the quest again
+    pub completed_quests: LookupMap<AccountId, BTreeMap<u64, QuestCompletion>>,
+    //DAO that makes the quest registry and scoring changes instead of the owner, if any
+    pub governance_account: Option<AccountId>,
+    //history of the score of each account, a checkpoint is written on every change
+    pub score_checkpoints: LookupMap<AccountId, Vector<ScoreCheckpoint>>,
+    //append-only log of the reputation changes of each account
+    pub reputation_history: LookupMap<AccountId, Vector<ReputationChange>>,
+    //merkle snapshots of the reputation and the one being built, if any
+    pub snapshots: UnorderedMap<u64, Snapshot>,
+    pub snapshot_in_progress: Option<u64>,
+    //balance of the reward pool of each quest, in the token of its reward
+    pub reward_pools: LookupMap<u64, Balance>,
+    //amount of its reward budget each quest has paid
+    pub quest_rewards_paid: LookupMap<u64, Balance>,
+    //rewards whose transfer failed, by account
+    pub unclaimed_rewards: LookupMap<AccountId, Vec<UnclaimedReward>>,
+    //rewards held until the account proves it is human, by account
+    pub pending_rewards: LookupMap<AccountId, Vec<UnclaimedReward>>,
 }
 
 #[near_bindgen]
@@ -121,19 +273,67 @@ impl Contract {
     #[init]
     pub fn new(owner_id: AccountId, metadata: NFTContractMetadata) -> Self {
         //create a variable of type Self with all the fields initialized. 
-        let this = Self {
+        let mut this = Self {
             tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
             tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
             token_metadata_by_id: UnorderedMap::new(
                 StorageKey::TokenMetadataById.try_to_vec().unwrap(),
             ),
+            treasury_id: owner_id.clone(),
             owner_id,
             metadata: LazyOption::new(
                 StorageKey::NFTContractMetadata.try_to_vec().unwrap(),
                 Some(&metadata),
-            )
+            ),
+            storage_accounts: LookupMap::new(StorageKey::StorageAccounts.try_to_vec().unwrap()),
+            quests: UnorderedMap::new(StorageKey::Quests.try_to_vec().unwrap()),
+            issuers: UnorderedMap::new(StorageKey::Issuers.try_to_vec().unwrap()),
+            oracle_keys: UnorderedSet::new(StorageKey::OracleKeys.try_to_vec().unwrap()),
+            used_nonces: LookupSet::new(StorageKey::UsedNonces.try_to_vec().unwrap()),
+            category_weights: UnorderedMap::new(StorageKey::CategoryWeights.try_to_vec().unwrap()),
+            seasons: UnorderedMap::new(StorageKey::Seasons.try_to_vec().unwrap()),
+            season_scores: LookupMap::new(StorageKey::SeasonScores.try_to_vec().unwrap()),
+            season_archives: LookupMap::new(StorageKey::SeasonArchives.try_to_vec().unwrap()),
+            seasons_by_quest: LookupMap::new(StorageKey::SeasonsByQuest.try_to_vec().unwrap()),
+            quest_completions: LookupMap::new(StorageKey::QuestCompletions.try_to_vec().unwrap()),
+            tokens_per_type: LookupMap::new(StorageKey::TokensPerType.try_to_vec().unwrap()),
+            tokens_to_mint_counter: LazyOption::new(StorageKey::TokensToMintCounter.try_to_vec().unwrap(), Some(&0)),
+            sorted_token_ids: TreeMap::new(StorageKey::SortedTokenIds.try_to_vec().unwrap()),
+            sorted_tokens_per_owner: TreeMap::new(StorageKey::SortedTokensPerOwner.try_to_vec().unwrap()),
+            sorted_tokens_per_quest: TreeMap::new(StorageKey::SortedTokensPerQuest.try_to_vec().unwrap()),
+            legacy_token_backfill: None,
+            token_id_scheme: TokenIdScheme::Sequential,
+            pending_claims: LookupMap::new(StorageKey::PendingClaims.try_to_vec().unwrap()),
+            next_claim_nonce: 0,
+            vouch_config: VouchConfig::default(),
+            vouches_by: LookupMap::new(StorageKey::VouchesBy.try_to_vec().unwrap()),
+            vouches_for: LookupMap::new(StorageKey::VouchesFor.try_to_vec().unwrap()),
+            bond_config: BondConfig::default(),
+            bonds: LookupMap::new(StorageKey::Bonds.try_to_vec().unwrap()),
+            unbondings: LookupMap::new(StorageKey::Unbondings.try_to_vec().unwrap()),
+            moderators: UnorderedSet::new(StorageKey::Moderators.try_to_vec().unwrap()),
+            human_gating: false,
+            pending_scores: LookupMap::new(StorageKey::PendingScores.try_to_vec().unwrap()),
+            human_proof_issuers: default_human_proof_issuers(),
+            verified_humans: LookupMap::new(StorageKey::VerifiedHumans.try_to_vec().unwrap()),
+            completed_quests: LookupMap::new(StorageKey::CompletedQuests.try_to_vec().unwrap()),
+            governance_account: None,
+            score_checkpoints: LookupMap::new(StorageKey::ScoreCheckpoints.try_to_vec().unwrap()),
+            reputation_history: LookupMap::new(StorageKey::ReputationHistory.try_to_vec().unwrap()),
+            snapshots: UnorderedMap::new(StorageKey::Snapshots.try_to_vec().unwrap()),
+            snapshot_in_progress: None,
+            reward_pools: LookupMap::new(StorageKey::RewardPools.try_to_vec().unwrap()),
+            quest_rewards_paid: LookupMap::new(StorageKey::QuestRewardsPaid.try_to_vec().unwrap()),
+            unclaimed_rewards: LookupMap::new(StorageKey::UnclaimedRewards.try_to_vec().unwrap()),
+            pending_rewards: LookupMap::new(StorageKey::PendingRewards.try_to_vec().unwrap()),
         };
 
+        //add the quests the contract starts with to the registry
+        for (quest_id, quest) in default_quests() {
+            this.quests.insert(&quest_id, &quest);
+        }
+        env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.to_le_bytes());
+
         //return the Contract object
         this
     }
@@ -0,0 +1,131 @@
+use crate::*;
+
+//raw storage key of the version of the contract state, the state of the first deployment has no version (0)
+pub(crate) const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+//version of the state written by this code
+pub(crate) const STATE_VERSION: u64 = 1;
+//number of legacy tokens backfilled by a call to migrate_tokens when no limit is passed in
+const DEFAULT_MIGRATION_STEPS: u64 = 100;
+
+//layout of the contract state of the first deployment (version 0), before the storage balances, the quest registry
+//and the rest of the reputation state were added
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct ContractV0 {
+    pub(crate) owner_id: AccountId,
+    pub(crate) tokens_per_owner: LookupMap<AccountId, UnorderedSet<TokenId>>,
+    pub(crate) tokens_by_id: LookupMap<TokenId, Token>,
+    pub(crate) token_metadata_by_id: UnorderedMap<TokenId, TokenMetadata>,
+    pub(crate) metadata: LazyOption<NFTContractMetadata>,
+}
+
+#[near_bindgen]
+impl Contract {
+    // Migrate the state written by an older version of the contract, it is called by the contract itself when the new
+    // code is deployed (near deploy ... --initFunction migrate --initArgs '{}'). The tokens are kept as they are and the
+    // new collections start empty, then migrate_tokens backfills the indexes of the tokens minted before the migration.
+    // The reputation history and the score checkpoints have their own storage prefixes, so later migrations keep them
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        assert_self();
+        let version = env::storage_read(STATE_VERSION_KEY)
+            .map(|bytes| u64::from_le_bytes(bytes.try_into().expect("Invalid state version")))
+            .unwrap_or(0);
+
+        match version {
+            0 => {
+                let old: ContractV0 = env::state_read().expect("The contract is not initialized");
+                let mut this = Self::new(old.owner_id, old.metadata.get().expect("The contract has no metadata"));
+                this.tokens_per_owner = old.tokens_per_owner;
+                this.tokens_by_id = old.tokens_by_id;
+                this.token_metadata_by_id = old.token_metadata_by_id;
+                this.metadata = old.metadata;
+
+                //the old token IDs were the number of tokens minted, the counter goes on from there
+                let minted = this.token_metadata_by_id.len();
+                this.tokens_to_mint_counter.set(&minted);
+                this.legacy_token_backfill = if minted > 0 { Some(0) } else { None };
+                this
+            }
+            _ => panic!("The state is already migrated"),
+        }
+    }
+
+    // Backfill the token indexes (tokens of each quest, sorted token IDs, quest completions, verified humans and score checkpoints) with the tokens minted
+    // before the migration, only the owner can call it. Each call handles up to limit tokens and the next call resumes
+    // it, tokens can't be burned and quests can't be claimed or awarded until it is done. Returns true once every token is backfilled
+    pub fn migrate_tokens(&mut self, limit: Option<u64>) -> bool {
+        self.assert_owner();
+        let mut index = match self.legacy_token_backfill {
+            Some(index) => index,
+            None => return true,
+        };
+
+        let minted = self.token_metadata_by_id.len();
+        let end = std::cmp::min(index + limit.unwrap_or(DEFAULT_MIGRATION_STEPS), minted);
+        while index < end {
+            let token_id = self.token_metadata_by_id.keys_as_vector().get(index).unwrap();
+            self.internal_backfill_token(&token_id);
+            index += 1;
+        }
+
+        self.legacy_token_backfill = if index < minted { Some(index) } else { None };
+        self.legacy_token_backfill.is_none()
+    }
+}
+
+impl Contract {
+    //make sure the tokens minted before the migration are backfilled: the claims read the quest completions, the supply of the
+    //quests and the verified humans that migrate_tokens writes, so until it is done they could claim a quest a second time
+    pub(crate) fn assert_tokens_migrated(&self) {
+        assert!(self.legacy_token_backfill.is_none(), "Quests can't be claimed until migrate_tokens is done");
+    }
+
+    //add a token minted before the migration to the indexes, tokens already in them are left as they are
+    fn internal_backfill_token(&mut self, token_id: &TokenId) {
+        self.sorted_token_ids.insert(&token_sort_key(token_id), &());
+        if let Some(token) = self.tokens_by_id.get(token_id) {
+            self.sorted_tokens_per_owner.insert(&owner_sort_key(&token.owner_id, token_id), &());
+        }
+
+        let quest_id = match self.internal_quest_id_of(token_id) {
+            Some(quest_id) => quest_id,
+            None => return self.internal_backfill_por(token_id),
+        };
+        if self.internal_add_token_to_quest(quest_id, token_id) {
+            let completions = self.quest_completions.get(&quest_id).unwrap_or(0);
+            self.quest_completions.insert(&quest_id, &(completions + 1));
+        }
+
+        let owner_id = match self.tokens_by_id.get(token_id) {
+            Some(token) => token.owner_id,
+            None => return,
+        };
+        //the holder of an old quest token is taken as the account that completed the quest
+        if self.internal_quest_badge_of(&owner_id, quest_id).is_none() {
+            self.internal_add_quest_completion(&owner_id, quest_id, token_id);
+        }
+
+        //the holders of an old I'm Human token are verified humans, their tokens don't store the proof
+        let human_quest = self.quests.get(&quest_id).is_some_and(|quest| quest.verifier == QuestVerifier::ImHuman);
+        if human_quest && !self.verified_humans.contains_key(&owner_id) {
+            self.verified_humans.insert(&owner_id, &None);
+        }
+    }
+
+    //write the starting score checkpoint of a Proof of Reputation minted before the migration, unless its score changed since
+    fn internal_backfill_por(&mut self, token_id: &TokenId) {
+        let owner_id = match self.tokens_by_id.get(token_id) {
+            Some(token) => token.owner_id,
+            None => return,
+        };
+        if self.score_checkpoints.contains_key(&owner_id) {
+            return;
+        }
+        if let Some((por_token_id, por)) = self.internal_get_por(&owner_id) {
+            if por_token_id == *token_id && por.score > 0 {
+                self.internal_start_checkpoints(&owner_id, por.score);
+            }
+        }
+    }
+}
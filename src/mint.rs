@@ -1,777 +1,597 @@
 use crate::*;
-use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::serde_json::{json,from_str};
-use near_sdk::{env,ext_contract, Balance,Gas, near_bindgen, AccountId, PromiseOrValue, PromiseResult, PanicOnDefault, log, BorshStorageKey, require};
-use near_sdk::json_types::{U128, U64};
-use near_sdk::Promise;
-use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use std::collections::HashMap;
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::{Gas, PromiseResult};
+use crate::governance::dao_roles_of;
 
 pub const IMAGE: &str = "QmdBddzRiQfWDs5uAovq4jxoBtsAKeJAipoqHAefmhoLBs";
+//title of the token that keeps track of the reputation of an account
+pub const POR_TITLE: &str = "Proof Of Reputation NFT";
+//GAS attached to the cross contract call that verifies a quest and to its callback
+pub(crate) const GAS_FOR_QUEST_CHECK: Gas = Gas(100_000_000_000_000);
+
+//typed reason why a quest claim did not mint anything
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimError {
+    //the quest doesn't exist
+    QuestNotFound,
+    //the quest exists but is not accepting claims
+    QuestInactive,
+    //the account already has the token of the quest
+    AlreadyClaimed,
+    //the account hasn't completed the quests required before this one
+    PrerequisitesMissing,
+    //the quest belongs to seasons and none of them is open
+    OutOfSeason,
+    //the quest has reached its maximum number of completions
+    SupplyExhausted,
+    //the quest is repeatable but the cooldown since the last completion has not finished
+    CooldownActive,
+    //another claim of the quest by the same account is waiting for its verification call
+    ClaimPending,
+    //the verification call answered that the account has not completed the quest
+    NotEligible,
+    //the verification call failed so we couldn't know if the quest was completed
+    VerifierFailed,
+    //the claim expired and was replaced before its verification call answered
+    ClaimExpired,
+    //the quest is awarded by issuers, it can't be claimed with nft_mint_quest
+    IssuerOnly,
+    //the quest is verified by an oracle, it must be claimed with claim_signed_quest
+    SignedClaimOnly,
+    //the account awarding the quest is not an issuer
+    NotIssuer,
+    //the issuer is not allowed to award the quest
+    IssuerNotAllowed,
+    //the issuer has awarded all the quests of its quota
+    IssuerQuotaReached,
+}
+
+impl std::fmt::Display for ClaimError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let message = match self {
+            ClaimError::QuestNotFound => "Quest not found",
+            ClaimError::QuestInactive => "Quest is not active",
+            ClaimError::AlreadyClaimed => "Quest token has already been minted",
+            ClaimError::PrerequisitesMissing => "The prerequisites of the quest have not been completed",
+            ClaimError::OutOfSeason => "The quest can't be claimed outside of its season",
+            ClaimError::SupplyExhausted => "The quest has reached its maximum number of completions",
+            ClaimError::CooldownActive => "The cooldown of the quest has not finished yet",
+            ClaimError::ClaimPending => "A claim of this quest is already in progress",
+            ClaimError::NotEligible => "The account has not completed the quest",
+            ClaimError::VerifierFailed => "The quest verification call failed",
+            ClaimError::ClaimExpired => "The claim expired before its verification call answered",
+            ClaimError::IssuerOnly => "This quest can only be awarded by an issuer",
+            ClaimError::SignedClaimOnly => "This quest must be claimed with claim_signed_quest",
+            ClaimError::NotIssuer => "Only issuers can award quests",
+            ClaimError::IssuerNotAllowed => "The issuer is not allowed to award this quest",
+            ClaimError::IssuerQuotaReached => "The issuer has reached its quota",
+        };
+        f.write_str(message)
+    }
+}
+
+//result of a quest claim, returned by the callbacks of nft_mint_quest
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClaimOutcome {
+    Claimed {
+        quest_id: u64,
+        //tokens minted by the claim (the quest token and, for the first claim, the Proof of Reputation token)
+        token_ids: Vec<TokenId>,
+        //score added by the quest
+        score_delta: u64,
+        //score and number of quests completed after the claim
+        total_score: u64,
+        quests_completed: u64,
+    },
+    Failed {
+        quest_id: u64,
+        error: ClaimError,
+    },
+}
+
+//what goes into the token of a completed quest
+pub(crate) struct QuestBadge {
+    pub name: String,
+    pub description: String,
+    pub score: u64,
+    pub image: String,
+    //issuer that awarded the quest and URI of the evidence, for quests awarded with award_quest
+    pub issuer: Option<AccountId>,
+    pub evidence_uri: Option<String>,
+    //SBT that proved the humanity of the account, for quests verified with I-Am-Human
+    pub human_proof: Option<HumanProof>,
+}
+
+impl QuestBadge {
+    //the badge of a quest in the registry
+    pub(crate) fn from_quest(quest: &QuestConfig) -> Self {
+        QuestBadge {
+            name: quest.name.clone(),
+            description: quest.description.clone(),
+            score: quest.score,
+            image: quest.image.clone(),
+            issuer: None,
+            evidence_uri: None,
+            human_proof: None,
+        }
+    }
+}
+
+//make the cross contract call that tells if the account has completed a quest verified on-chain
+pub(crate) fn verification_call(verifier: &QuestVerifier, account_id: &AccountId) -> Promise {
+    let accountid = account_id.to_string();
+    match verifier {
+        // Im Human: the SBTs of the account with their issuer, class and expiry
+        QuestVerifier::ImHuman => ext_nft::sbt_tokens_by_owner(
+            accountid,
+            None,
+            None,
+            None,
+            Some(false),
+            "registry.i-am-human.near".parse::<AccountId>().unwrap(),
+            NO_DEPOSIT,
+            GAS_FOR_QUEST_CHECK,
+        ),
+        // stNEAR
+        QuestVerifier::StNear => ext_nft::ft_balance_of(
+            accountid,
+            "meta-pool.near".parse::<AccountId>().unwrap(),
+            NO_DEPOSIT,
+            GAS_FOR_QUEST_CHECK,
+        ),
+        // Meta Token
+        QuestVerifier::MetaToken => ext_nft::ft_balance_of(
+            accountid,
+            "meta-token.near".parse::<AccountId>().unwrap(),
+            NO_DEPOSIT,
+            GAS_FOR_QUEST_CHECK,
+        ),
+        // Voting Power
+        QuestVerifier::VotingPower => ext_nft::get_locked_balance(
+            accountid,
+            "meta-vote.near".parse::<AccountId>().unwrap(),
+            NO_DEPOSIT,
+            GAS_FOR_QUEST_CHECK,
+        ),
+        // DAO membership: the policy of the DAO, the account is looked for in its roles by the callback
+        QuestVerifier::DaoMember { dao_id, .. } => ext_nft::get_policy(
+            dao_id.clone(),
+            NO_DEPOSIT,
+            GAS_FOR_QUEST_CHECK,
+        ),
+        QuestVerifier::Issuer | QuestVerifier::Oracle => panic!("This quest is not verified with a cross contract call"),
+    }
+}
+
+//get the balance returned by ft_balance_of and get_locked_balance (as a JSON string or number)
+pub(crate) fn observed_value(result: &str) -> Option<u128> {
+    match serde_json::from_str(result).ok()? {
+        serde_json::Value::String(value) => value.parse().ok(),
+        serde_json::Value::Number(value) => value.as_u64().map(u128::from),
+        _ => None,
+    }
+}
+
+//get the value returned by the cross contract call that verified a quest, None if the call failed
+pub(crate) fn quest_check_result() -> Option<String> {
+    assert_eq!(
+        env::promise_results_count(),
+        1,
+        "Éste es un método callback"
+    );
+    match env::promise_result(0) {
+        PromiseResult::NotReady => unreachable!(),
+        PromiseResult::Failed => None,
+        PromiseResult::Successful(result) => Some(String::from_utf8(result).unwrap()),
+    }
+}
 
 #[near_bindgen]
 impl Contract {
 
-    // Method to mint a quest (NFT), receives as a parameter the type of quest that will be minted
+    // Method to mint a token with custom metadata, only the owner of the contract can call it
     #[payable]
-    pub fn nft_mint_quest(&mut self, quest_number: u64) -> Promise {
-        let receiver_id = env::signer_account_id();
+    pub fn nft_mint(
+        &mut self,
+        token_id: TokenId,
+        metadata: TokenMetadata,
+        receiver_id: AccountId,
+        perpetual_royalties: Option<HashMap<AccountId, u32>>,
+    ) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Only the owner can mint tokens"
+        );
         let initial_storage_usage = env::storage_usage();
-        let deposit = env::attached_deposit();
 
-        let mut has_por_nft = false;
-        let mut has_quest_nft = false;
+        // Copy the perpetual royalties (if any) into the royalty map of the token
+        let mut royalty = HashMap::new();
+        if let Some(perpetual_royalties) = perpetual_royalties {
+            assert!(perpetual_royalties.len() < 7, "Cannot add more than 6 perpetual royalty amounts");
+            royalty.extend(perpetual_royalties);
+        }
 
-        // Validate that the quest entered exists within the available list
-        if let Some(quest) = QuestType::from_number(quest_number) {
-            let (id, name, description, score, image) = quest.get_quest_info();
+        self.internal_mint(&token_id, metadata, &receiver_id, royalty);
 
-            // Get the list of tokens per user
-            let tokens_for_owner_set = self.tokens_per_owner.get(&receiver_id);
-            let tokens = if let Some(tokens_for_owner_set) = tokens_for_owner_set {
-                tokens_for_owner_set
-            } else {
-                near_sdk::collections::UnorderedSet::new(b"tokens".to_vec()) 
-            };
+        // Refund any excess storage attached by the owner
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        refund_deposit(required_storage_in_bytes);
+    }
 
+    // Method to mint a quest (NFT), receives as a parameter the id of the quest in the registry that will be minted
+    // The attached deposit is added to the storage balance of the account and the storage used by the mint is charged against it
+    // If the quest can't be claimed right now the deposit is given back and the claim resolves to a failed ClaimOutcome
+    // right away, without the verification call
+    #[payable]
+    pub fn nft_mint_quest(&mut self, quest_number: u64) -> PromiseOrValue<ClaimOutcome> {
+        self.assert_tokens_migrated();
+        let receiver_id = env::signer_account_id();
+        let deposit = env::attached_deposit();
 
-            // If the list of tokens is different from empty, then we look for whether the NFT of the quest to be minted already exists
-            if !tokens.is_empty() {
-                let start = u128::from(U128(0));
-                has_quest_nft = tokens.iter()
-                .skip(start as usize)
-                .filter_map(|token_id| {
-                    let token = self.nft_token(token_id.clone()).unwrap();
-                    let title = token.metadata.title.unwrap_or_default();
-                    if title == name.to_string() {
-                        Some(true)
-                    } else {
-                        Some(false)
-                    }
-                })
-                .any(|value| value);
-
-                has_por_nft = tokens.iter()
-                .skip(start as usize)
-                .filter_map(|token_id| {
-                    let token = self.nft_token(token_id.clone()).unwrap();
-                    let title = token.metadata.title.unwrap_or_default();
-                    if title == "Proof Of Reputation NFT".to_string() {
-                        Some(true)
-                    } else {
-                        Some(false)
-                    }
-                })
-                .any(|value| value);
+        // Validate that the quest entered exists within the registry and can be claimed by the account
+        // (the NFT of the quest doesn't exist yet or the quest can be repeated, its prerequisites are completed...)
+        // and that it is verified with a cross contract call
+        let claimable = self.internal_claimable_quest(&receiver_id, quest_number).and_then(|quest| match quest.verifier {
+            QuestVerifier::Issuer => Err(ClaimError::IssuerOnly),
+            QuestVerifier::Oracle => Err(ClaimError::SignedClaimOnly),
+            _ => Ok(quest),
+        });
+        let quest = match claimable {
+            Ok(quest) => quest,
+            Err(error) => {
+                if deposit > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(deposit);
+                }
+                return PromiseOrValue::Value(self.internal_log_claim_failed(&receiver_id, quest_number, error));
             }
+        };
+        // Keep the deposit as storage balance of the account and make sure it covers the claim
+        if deposit > 0 {
+            self.internal_storage_deposit(&receiver_id, deposit);
+        }
+        let required_cost = storage_cost(STORAGE_BYTES_PER_CLAIM);
+        assert!(
+            self.internal_storage_available(&receiver_id) >= required_cost,
+            "Must attach {} yoctoNEAR or deposit it with storage_deposit to cover storage",
+            required_cost,
+        );
 
-            // If it already exists then we stop the execution of the method
-            if has_quest_nft {
-            env::panic_str("Quest token has already been minted");
-            }
+        // Reserve the storage of the claim so other claims and withdrawals can't use it while the verification call is in flight
+        self.internal_reserve_storage(&receiver_id, STORAGE_BYTES_PER_CLAIM);
 
-            let accountid = env::signer_account_id().clone();
-
-            // Validate the type of quest that will be mined and make the cross contract call to the quest contract to verify that the challenge is completed.
-            // Im Human
-            if quest_number == 0 {
-                let call0 = ext_nft::is_human(
-                    accountid.clone().to_string(),
-                    "registry.i-am-human.near".parse::<AccountId>().unwrap(),
-                    NO_DEPOSIT,
-                    Gas(100_000_000_000_000)
-                );
-                let callback0 = ext_self::resolve_mint_im_human(
-                    name.to_string(), description.to_string(), score, image.to_string(), has_por_nft,
-                    "proof-of-reputation.near".parse::<AccountId>().unwrap(),
-                    deposit,
-                    Gas(100_000_000_000_000)
-                );
-                return call0.then(callback0);
-            }
-            // stNEAR
-            if quest_number == 1 {
-                let call1 = ext_nft::ft_balance_of(
-                    accountid.clone().to_string(),
-                    "meta-pool.near".parse::<AccountId>().unwrap(),
-                    NO_DEPOSIT,
-                    Gas(100_000_000_000_000)
-                );
-                let callback1 = ext_self::resolve_mint_st_near(
-                    name.to_string(), description.to_string(), score, image.to_string(), has_por_nft,
-                    "proof-of-reputation.near".parse::<AccountId>().unwrap(),
-                    deposit,
-                    Gas(100_000_000_000_000)
-                );
-                return  call1.then(callback1);
-            }
-            // Meta Token
-            if quest_number == 2 {
-                let call2 = ext_nft::ft_balance_of(
-                    accountid.clone().to_string(),
-                    "meta-token.near".parse::<AccountId>().unwrap(),
-                    NO_DEPOSIT,
-                    Gas(100_000_000_000_000)
-                );
-                let callback2 = ext_self::resolve_mint_meta_token(
-                    name.to_string(), description.to_string(), score, image.to_string(), has_por_nft,
-                    "proof-of-reputation.near".parse::<AccountId>().unwrap(),
-                    deposit,
-                    Gas(100_000_000_000_000)
-                );
-                return call2.then(callback2);
-            }
-            // Voting Power
-            if quest_number == 3 {
-                let call3 = ext_nft::get_locked_balance(
-                    accountid.clone().to_string(),
-                    "meta-vote.near".parse::<AccountId>().unwrap(),
-                    NO_DEPOSIT,
-                    Gas(100_000_000_000_000)
-                );
-                let callback3 = ext_self::resolve_mint_voting_power(
-                    name.to_string(), description.to_string(), score, image.to_string(), has_por_nft,
-                    "proof-of-reputation.near".parse::<AccountId>().unwrap(),
-                    deposit,
-                    Gas(100_000_000_000_000)
-                );
-                return call3.then(callback3);
-            }
-            env::panic_str("Token has already been minted");
-        } else {
-            env::panic_str("Quest not found");
-        }
+        // Block other claims of the same quest until the callback runs, the callback gives back the deposit if the claim fails
+        let nonce = self.internal_add_pending_claim(&receiver_id, quest_number, deposit, env::predecessor_account_id(), STORAGE_BYTES_PER_CLAIM);
 
+        // Make the cross contract call to the quest contract to verify that the challenge is completed.
+        let verification = verification_call(&quest.verifier, &receiver_id);
+        let callback = match quest.verifier {
+            QuestVerifier::ImHuman => ext_self::resolve_mint_im_human,
+            QuestVerifier::StNear => ext_self::resolve_mint_st_near,
+            QuestVerifier::MetaToken => ext_self::resolve_mint_meta_token,
+            QuestVerifier::VotingPower => ext_self::resolve_mint_voting_power,
+            QuestVerifier::DaoMember { .. } => ext_self::resolve_mint_dao_member,
+            QuestVerifier::Issuer | QuestVerifier::Oracle => unreachable!(),
+        };
+        verification
+            .then(callback(
+                quest_number,
+                receiver_id,
+                nonce,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_QUEST_CHECK,
+            ))
+            .into()
     }
 
     // The following methods obtain the response from the promise of each of the 4 types of XCC that was carried out previously, where it is validated that the challenge was completed to subsequently mint the token.
     // Each NFT will contain its own image and metadata.
     // In any case, it is verified if the main token has already been minted (Proof of Reputation) and the metadata score will be updated.
     // If you still do not have the main token, then a new one is created.
-    #[payable]
-    pub fn resolve_mint_im_human(&mut self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String {
-        assert_eq!(
-            env::promise_results_count(),
-            1,
-            "Éste es un método callback"
-        );
-        match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => {
-                return "Error al obtener el valor".to_string();
-            },
-            PromiseResult::Successful(result) => {
-                let value = std::str::from_utf8(&result).unwrap();
-                let receiver_id = env::signer_account_id();
-                let initial_storage_usage = env::storage_usage();
-                if value != "[]" {
-                    let mut new_token = TokenMetadata {
-                        title:  Some(name.to_string()), 
-                        description:  Some(description.to_string()),
-                        media:  Some(image.to_string()),
-                        expires_at: None,
-                        starts_at: None,
-                        copies: None,
-                        extra: None,
-                        issued_at: None,
-                        media_hash: None,
-                        reference: None,
-                        reference_hash: None,
-                        updated_at: None
+    // They are private callbacks that only receive the quest, the account that claimed it and the nonce of the pending claim: the metadata
+    // and score of the token come from the quest registry and the deposit of the claim from the pending claim, so nothing can be forged by the caller.
+    // The pending claim is cleared and the claim is checked again against the current state before minting. If the pending claim
+    // expired and was replaced by a newer one, the late callback fails without minting and leaves the newer claim in place.
+    // If the challenge was not completed or the call failed, the deposit of the claim is given back to the account that paid it.
+    // The storage reserved for the claim is released or settled with the storage really used, so they don't panic.
+    // The result of the claim is returned as a ClaimOutcome.
+    #[private]
+    pub fn resolve_mint_im_human(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        self.internal_resolve_claim(quest_id, account_id, nonce)
+    }
+
+    #[private]
+    pub fn resolve_mint_st_near(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        self.internal_resolve_claim(quest_id, account_id, nonce)
+    }
+
+    #[private]
+    pub fn resolve_mint_meta_token(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        self.internal_resolve_claim(quest_id, account_id, nonce)
+    }
+
+    #[private]
+    pub fn resolve_mint_voting_power(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        self.internal_resolve_claim(quest_id, account_id, nonce)
+    }
+
+    #[private]
+    pub fn resolve_mint_dao_member(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        self.internal_resolve_claim(quest_id, account_id, nonce)
+    }
+}
+
+impl Contract {
+    // Shared body of the resolve_mint_* callbacks.
+    fn internal_resolve_claim(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome {
+        // #[private] is only enforced in the compiled contract, the check is repeated so it also holds in the unit tests
+        assert_self();
+        let claim = match self.internal_remove_pending_claim(&account_id, quest_id, nonce) {
+            Some(claim) => claim,
+            None => return self.internal_log_claim_failed(&account_id, quest_id, ClaimError::ClaimExpired),
+        };
+
+        match quest_check_result() {
+            None => self.internal_fail_claim(&account_id, quest_id, ClaimError::VerifierFailed, &claim),
+            Some(value) => {
+                if self.internal_is_eligible(quest_id, &account_id, &value) {
+                    let quest = self.quests.get(&quest_id).expect("Quest not found");
+                    let human_proof = match quest.verifier {
+                        QuestVerifier::ImHuman => self.internal_human_proofs(&value).and_then(|proofs| proofs.into_iter().next()),
+                        _ => None,
                     };
-                
-                        let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                
-                        let mut empty_quest = Quest {
-                            score : 0,
-                            date: 0
-                        };
-                
-                        empty_quest.score = score;
-                        empty_quest.date = env::block_timestamp();
-                
-                        let mut extra_data_string = serde_json::to_string(&empty_quest).unwrap();
-                        extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                        new_token.extra = Some(extra_data_string);
-                
-                        let mut royalty = HashMap::new();
-                
-                        let token = Token {
-                            owner_id: receiver_id.clone(),
-                            approved_account_ids: Default::default(),
-                            next_approval_id: 0,
-                            royalty
-                        };
-                
-                        assert!(
-                            self.tokens_by_id.insert(&token_id, &token).is_none(),
-                            "Token already exists"
-                        );
-                
-                        self.token_metadata_by_id.insert(&token_id, &new_token);
-                        self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                
-                        if has_por_nft {
-                            let tokens_for_owner_set = self.tokens_per_owner.get(&receiver_id);
-                            let tokens = if let Some(tokens_for_owner_set) = tokens_for_owner_set {
-                                tokens_for_owner_set
-                            } else {
-                                near_sdk::collections::UnorderedSet::new(b"tokens".to_vec()) 
-                            };
-                
-                            let start = u128::from(U128(0));
-                            let mut por_nft = tokens.iter()
-                            .skip(start as usize) 
-                            .filter_map(|token_id| {
-                                let token = self.nft_token(token_id.clone()).unwrap();
-                                let title = token.metadata.title.unwrap_or_default();
-                                if title == "Proof Of Reputation NFT".to_string() {
-                                    Some(self.nft_token(token_id.clone()).unwrap())
-                                } else {
-                                    None
-                                }
-                            })
-                            .next().unwrap();
-                
-                            let mut metadata = self.token_metadata_by_id.get(&por_nft.token_id).unwrap();
-                            let newextradata = str::replace(&metadata.extra.as_ref().unwrap().to_string(), "'", "\"");
-                            let mut extradatajson: PoR = serde_json::from_str(&newextradata).unwrap();
-                
-                            extradatajson.score = extradatajson.score+score;
-                            extradatajson.quest_completed = extradatajson.quest_completed+1;
-                            extradatajson.date_last_quest = env::block_timestamp();
-                
-                            let mut extra_string = serde_json::to_string(&extradatajson).unwrap();
-                            extra_string = str::replace(&extra_string, "\"", "'");
-                            metadata.extra = Some(extra_string.clone());
-                
-                            self.token_metadata_by_id.insert(&por_nft.token_id, &metadata);                
-                        } else {                
-                            let mut new_token = TokenMetadata {
-                                title:  Some("Proof Of Reputation NFT".to_string()), 
-                                description:  Some("This nft contains the information with the progress within the network".to_string()),
-                                media:  Some(IMAGE.to_string()),
-                                expires_at: None,
-                                starts_at: None,
-                                copies: None,
-                                extra: None,
-                                issued_at: None,
-                                media_hash: None,
-                                reference: None,
-                                reference_hash: None,
-                                updated_at: None
-                            };
-                    
-                            let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                    
-                            let mut empty_poh = PoR {
-                                score : 0,
-                                quest_completed : 0,
-                                date_last_quest : 0
-                            };
-                    
-                            empty_poh.score = score;
-                            empty_poh.quest_completed = 1;
-                            empty_poh.date_last_quest = env::block_timestamp();
-                    
-                            let mut extra_data_string = serde_json::to_string(&empty_poh).unwrap();
-                            extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                            new_token.extra = Some(extra_data_string);
-                    
-                            let mut royalty = HashMap::new();
-                    
-                            let token = Token {
-                                owner_id: receiver_id.clone(),
-                                approved_account_ids: Default::default(),
-                                next_approval_id: 0,
-                                royalty
-                            };
-                    
-                            assert!(
-                                self.tokens_by_id.insert(&token_id, &token).is_none(),
-                                "Token already exists"
-                            );
-                    
-                            self.token_metadata_by_id.insert(&token_id, &new_token);
-                            self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                        }
-                
-                    let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-                    refund_deposit(required_storage_in_bytes);
+                    let badge = QuestBadge { human_proof, ..QuestBadge::from_quest(&quest) };
+                    self.internal_complete_claim(&account_id, quest_id, badge, &claim)
+                } else {
+                    self.internal_fail_claim(&account_id, quest_id, ClaimError::NotEligible, &claim)
                 }
-                return "Success in obtaining I am human".to_string();
             }
         }
     }
 
-    #[payable]
-    pub fn resolve_mint_st_near(&mut self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String {
-        assert_eq!(
-            env::promise_results_count(),
-            1,
-            "Éste es un método callback"
-        );
-        match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => {
-                return "Error al obtener el valor".to_string();
-            },
-            PromiseResult::Successful(result) => {
-                let value = std::str::from_utf8(&result).unwrap();
-                let receiver_id = env::signer_account_id();
-                let initial_storage_usage = env::storage_usage();
-                if value != "0" {
-                    let mut new_token = TokenMetadata {
-                        title:  Some(name.to_string()), 
-                        description:  Some(description.to_string()),
-                        media:  Some(image.to_string()),
-                        expires_at: None,
-                        starts_at: None,
-                        copies: None,
-                        extra: None,
-                        issued_at: None,
-                        media_hash: None,
-                        reference: None,
-                        reference_hash: None,
-                        updated_at: None
-                    };
-                
-                        let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                
-                        let mut empty_quest = Quest {
-                            score : 0,
-                            date: 0
-                        };
-                
-                        empty_quest.score = score;
-                        empty_quest.date = env::block_timestamp();
-                
-                        let mut extra_data_string = serde_json::to_string(&empty_quest).unwrap();
-                        extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                        new_token.extra = Some(extra_data_string);
-                
-                        let mut royalty = HashMap::new();
-                
-                        let token = Token {
-                            owner_id: receiver_id.clone(),
-                            approved_account_ids: Default::default(),
-                            next_approval_id: 0,
-                            royalty
-                        };
-                
-                        assert!(
-                            self.tokens_by_id.insert(&token_id, &token).is_none(),
-                            "Token already exists"
-                        );
-                
-                        self.token_metadata_by_id.insert(&token_id, &new_token);
-                        self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                
-                        if has_por_nft {
-                            let tokens_for_owner_set = self.tokens_per_owner.get(&receiver_id);
-                            let tokens = if let Some(tokens_for_owner_set) = tokens_for_owner_set {
-                                tokens_for_owner_set
-                            } else {
-                                near_sdk::collections::UnorderedSet::new(b"tokens".to_vec()) 
-                            };
-                
-                            let start = u128::from(U128(0));
-                            let mut por_nft = tokens.iter()
-                            .skip(start as usize) 
-                            .filter_map(|token_id| {
-                                let token = self.nft_token(token_id.clone()).unwrap();
-                                let title = token.metadata.title.unwrap_or_default();
-                                if title == "Proof Of Reputation NFT".to_string() {
-                                    Some(self.nft_token(token_id.clone()).unwrap())
-                                } else {
-                                    None
-                                }
-                            })
-                            .next().unwrap();
-                
-                            let mut metadata = self.token_metadata_by_id.get(&por_nft.token_id).unwrap();
-                            let newextradata = str::replace(&metadata.extra.as_ref().unwrap().to_string(), "'", "\"");
-                            let mut extradatajson: PoR = serde_json::from_str(&newextradata).unwrap();
-                
-                            extradatajson.score = extradatajson.score+score;
-                            extradatajson.quest_completed = extradatajson.quest_completed+1;
-                            extradatajson.date_last_quest = env::block_timestamp();
-                
-                            let mut extra_string = serde_json::to_string(&extradatajson).unwrap();
-                            extra_string = str::replace(&extra_string, "\"", "'");
-                            metadata.extra = Some(extra_string.clone());
-                
-                            self.token_metadata_by_id.insert(&por_nft.token_id, &metadata);                
-                        } else {                
-                            let mut new_token = TokenMetadata {
-                                title:  Some("Proof Of Reputation NFT".to_string()), 
-                                description:  Some("This nft contains the information with the progress within the network".to_string()),
-                                media:  Some(IMAGE.to_string()),
-                                expires_at: None,
-                                starts_at: None,
-                                copies: None,
-                                extra: None,
-                                issued_at: None,
-                                media_hash: None,
-                                reference: None,
-                                reference_hash: None,
-                                updated_at: None
-                            };
-                    
-                            let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                    
-                            let mut empty_poh = PoR {
-                                score : 0,
-                                quest_completed : 0,
-                                date_last_quest : 0
-                            };
-                    
-                            empty_poh.score = score;
-                            empty_poh.quest_completed = 1;
-                            empty_poh.date_last_quest = env::block_timestamp();
-                    
-                            let mut extra_data_string = serde_json::to_string(&empty_poh).unwrap();
-                            extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                            new_token.extra = Some(extra_data_string);
-                    
-                            let mut royalty = HashMap::new();
-                    
-                            let token = Token {
-                                owner_id: receiver_id.clone(),
-                                approved_account_ids: Default::default(),
-                                next_approval_id: 0,
-                                royalty
-                            };
-                    
-                            assert!(
-                                self.tokens_by_id.insert(&token_id, &token).is_none(),
-                                "Token already exists"
-                            );
-                    
-                            self.token_metadata_by_id.insert(&token_id, &new_token);
-                            self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                        }
-                
-                    let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-                    refund_deposit(required_storage_in_bytes);
-                }
-                return "Success to get stNEAR".to_string();
-            }
+    // Mint the NFT of the quest once the verification call confirmed it was completed.
+    // Other claims may have used the last completions of the quest (or completed it again) while the call was in flight, so the claim is checked again.
+    pub(crate) fn internal_complete_claim(
+        &mut self,
+        receiver_id: &AccountId,
+        quest_id: u64,
+        badge: QuestBadge,
+        claim: &PendingClaim,
+    ) -> ClaimOutcome {
+        let quest = self.quests.get(&quest_id).expect("Quest not found");
+        if let Some(error) = self.internal_claim_error(receiver_id, quest_id, &quest) {
+            return self.internal_fail_claim(receiver_id, quest_id, error, claim);
         }
+        self.internal_mint_quest(receiver_id, quest_id, badge, Some(claim.reserved_bytes))
     }
 
-    #[payable]
-    pub fn resolve_mint_meta_token(&mut self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String {
-        assert_eq!(
-            env::promise_results_count(),
-            1,
-            "Éste es un método callback"
-        );
-        match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => {
-                return "Error al obtener el valor".to_string();
-            },
-            PromiseResult::Successful(result) => {
-                let value = std::str::from_utf8(&result).unwrap();
-                log!("Meta Token: {}",value);
-
-                let receiver_id = env::signer_account_id();
-                let initial_storage_usage = env::storage_usage();
-                if value != "0" {
-                    let mut new_token = TokenMetadata {
-                        title:  Some(name.to_string()), 
-                        description:  Some(description.to_string()),
-                        media:  Some(image.to_string()),
-                        expires_at: None,
-                        starts_at: None,
-                        copies: None,
-                        extra: None,
-                        issued_at: None,
-                        media_hash: None,
-                        reference: None,
-                        reference_hash: None,
-                        updated_at: None
-                    };
-                
-                        let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                
-                        let mut empty_quest = Quest {
-                            score : 0,
-                            date: 0
-                        };
-                
-                        empty_quest.score = score;
-                        empty_quest.date = env::block_timestamp();
-                
-                        let mut extra_data_string = serde_json::to_string(&empty_quest).unwrap();
-                        extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                        new_token.extra = Some(extra_data_string);
-                
-                        let mut royalty = HashMap::new();
-                
-                        let token = Token {
-                            owner_id: receiver_id.clone(),
-                            approved_account_ids: Default::default(),
-                            next_approval_id: 0,
-                            royalty
-                        };
-                
-                        assert!(
-                            self.tokens_by_id.insert(&token_id, &token).is_none(),
-                            "Token already exists"
-                        );
-                
-                        self.token_metadata_by_id.insert(&token_id, &new_token);
-                        self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                        if has_por_nft {
-                            let tokens_for_owner_set = self.tokens_per_owner.get(&receiver_id);
-                            let tokens = if let Some(tokens_for_owner_set) = tokens_for_owner_set {
-                                tokens_for_owner_set
-                            } else {
-                                near_sdk::collections::UnorderedSet::new(b"tokens".to_vec()) 
-                            };
-                
-                            let start = u128::from(U128(0));
-                            let mut por_nft = tokens.iter()
-                            .skip(start as usize) 
-                            .filter_map(|token_id| {
-                                let token = self.nft_token(token_id.clone()).unwrap();
-                                let title = token.metadata.title.unwrap_or_default();
-                                if title == "Proof Of Reputation NFT".to_string() {
-                                    Some(self.nft_token(token_id.clone()).unwrap())
-                                } else {
-                                    None
-                                }
-                            })
-                            .next().unwrap();
-                
-                            let mut metadata = self.token_metadata_by_id.get(&por_nft.token_id).unwrap();
-                            let newextradata = str::replace(&metadata.extra.as_ref().unwrap().to_string(), "'", "\"");
-                            let mut extradatajson: PoR = serde_json::from_str(&newextradata).unwrap();
-                
-                            extradatajson.score = extradatajson.score+score;
-                            extradatajson.quest_completed = extradatajson.quest_completed+1;
-                            extradatajson.date_last_quest = env::block_timestamp();
-                
-                            let mut extra_string = serde_json::to_string(&extradatajson).unwrap();
-                            extra_string = str::replace(&extra_string, "\"", "'");
-                            metadata.extra = Some(extra_string.clone());
-                
-                            self.token_metadata_by_id.insert(&por_nft.token_id, &metadata);
-                
-                        } else {
-                            let mut new_token = TokenMetadata {
-                                title:  Some("Proof Of Reputation NFT".to_string()), 
-                                description:  Some("This nft contains the information with the progress within the network".to_string()),
-                                media:  Some(IMAGE.to_string()),
-                                expires_at: None,
-                                starts_at: None,
-                                copies: None,
-                                extra: None,
-                                issued_at: None,
-                                media_hash: None,
-                                reference: None,
-                                reference_hash: None,
-                                updated_at: None
-                            };
-                    
-                            let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                    
-                            let mut empty_poh = PoR {
-                                score : 0,
-                                quest_completed : 0,
-                                date_last_quest : 0
-                            };
-                    
-                            empty_poh.score = score;
-                            empty_poh.quest_completed = 1;
-                            empty_poh.date_last_quest = env::block_timestamp();
-                    
-                            let mut extra_data_string = serde_json::to_string(&empty_poh).unwrap();
-                            extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                            new_token.extra = Some(extra_data_string);
-                    
-                            let mut royalty = HashMap::new();
-                    
-                            let token = Token {
-                                owner_id: receiver_id.clone(),
-                                approved_account_ids: Default::default(),
-                                next_approval_id: 0,
-                                royalty
-                            };
-                    
-                            assert!(
-                                self.tokens_by_id.insert(&token_id, &token).is_none(),
-                                "Token already exists"
-                            );
-                    
-                            self.token_metadata_by_id.insert(&token_id, &new_token);
-                            self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                        }
-                
-                    let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-                    refund_deposit(required_storage_in_bytes);
+    // Mint the NFT of the quest and add its score to the Proof of Reputation NFT (creating it if the account doesn't have one yet).
+    // The storage used by the mint is charged against the storage balance of the account: for claims that reserved their
+    // storage when they started (Some bytes reserved) the reservation is settled without panicking, otherwise the storage
+    // balance must cover the mint.
+    pub(crate) fn internal_mint_quest(&mut self, receiver_id: &AccountId, quest_id: u64, badge: QuestBadge, reserved_bytes: Option<u64>) -> ClaimOutcome {
+        let initial_storage_usage = env::storage_usage();
+        let config = self.quests.get(&quest_id).expect("Quest not found");
+        let category = config.category;
+        let human_proof = badge.human_proof.clone();
+
+        let now = env::block_timestamp();
+
+        let (score, mut token_ids, first_completion) = match self.internal_quest_badge_of(receiver_id, quest_id) {
+            // Repeatable quests completed again update the counter and streak of the badge minted for the account, even if it was transferred
+            Some((token_id, mut quest)) => {
+                let repeat = config.repeat.as_ref().unwrap_or_else(|| panic!("{}", ClaimError::AlreadyClaimed));
+                let streak = if now <= quest.date + repeat.streak_window.0 {
+                    quest.streak.unwrap_or(1) + 1
+                } else {
+                    1
+                };
+                let score = repeat.apply_multiplier(badge.score, streak);
+
+                quest.score += score;
+                quest.date = now;
+                quest.times_completed = Some(quest.times_completed.unwrap_or(1) + 1);
+                quest.streak = Some(streak);
+                if badge.human_proof.is_some() {
+                    quest.human_proof = badge.human_proof;
                 }
 
-                return "Success to get Meta Token".to_string();
+                let mut metadata = self.token_metadata_by_id.get(&token_id).unwrap();
+                metadata.extra = Some(to_extra(&quest));
+                metadata.updated_at = Some(now / 1_000_000);
+                self.token_metadata_by_id.insert(&token_id, &metadata);
+                (score, vec![token_id], false)
             }
+            None => {
+                // Count the completion and give the early-bird bonus to the first completers
+                let completions = self.quest_completions.get(&quest_id).unwrap_or(0);
+                self.quest_completions.insert(&quest_id, &(completions + 1));
+                let bonus = match config.early_bird {
+                    Some(early_bird) if completions < early_bird.completers => early_bird.bonus,
+                    _ => 0,
+                };
+                let score = match config.repeat.as_ref() {
+                    Some(repeat) => repeat.apply_multiplier(badge.score, 1),
+                    None => badge.score,
+                } + bonus;
+
+                let quest = Quest {
+                    score,
+                    date: now,
+                    quest_id: Some(quest_id),
+                    issuer: badge.issuer,
+                    times_completed: config.repeat.as_ref().map(|_| 1),
+                    streak: config.repeat.as_ref().map(|_| 1),
+                    human_proof: badge.human_proof,
+                };
+
+                let new_token = TokenMetadata {
+                    title: Some(badge.name),
+                    description: Some(badge.description),
+                    media: Some(badge.image),
+                    expires_at: None,
+                    starts_at: None,
+                    copies: Some(completions + 1),
+                    extra: Some(to_extra(&quest)),
+                    issued_at: None,
+                    media_hash: None,
+                    reference: badge.evidence_uri,
+                    reference_hash: None,
+                    updated_at: None
+                };
+
+                let token_id = self.internal_next_quest_token_id(quest_id, completions + 1);
+                self.internal_mint(&token_id, new_token, receiver_id, HashMap::new());
+                self.internal_add_token_to_quest(quest_id, &token_id);
+                self.internal_add_quest_completion(receiver_id, quest_id, &token_id);
+                (score, vec![token_id], true)
+            }
+        };
+        // Completing I'm Human makes the account a verified human, the proof stays with the account if the badge is transferred
+        let human_quest = config.verifier == QuestVerifier::ImHuman;
+        if human_quest {
+            let proof = human_proof.or_else(|| self.verified_humans.get(receiver_id).flatten());
+            self.verified_humans.insert(receiver_id, &proof);
         }
-    }
 
-    #[payable]
-    pub fn resolve_mint_voting_power(&mut self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String {
-        assert_eq!(
-            env::promise_results_count(),
-            1,
-            "Éste es un método callback"
+        // Under the human gating policy the score (and the reward) of accounts that are not verified humans is held as pending
+        let held = !human_quest && self.internal_is_score_gated(receiver_id);
+        let credited = if held {
+            self.internal_add_pending_score(receiver_id, quest_id, category, score);
+            0
+        } else {
+            score
+        };
+
+        // The Proof of Reputation token is looked up now (and not when the claim started) so parallel claims don't create two of them
+        let mut por = if let Some(por_token_id) = self.internal_token_with_title(receiver_id, POR_TITLE) {
+            let mut metadata = self.token_metadata_by_id.get(&por_token_id).unwrap();
+            let mut por: PoR = from_extra(metadata.extra.as_ref().unwrap());
+
+            por.score += credited;
+            *por.categories.entry(category).or_insert(0) += credited;
+            if first_completion {
+                por.quest_completed += 1;
+            }
+            por.date_last_quest = env::block_timestamp();
+
+            metadata.extra = Some(to_extra(&por));
+            self.token_metadata_by_id.insert(&por_token_id, &metadata);
+            por
+        } else {
+            let por = PoR {
+                score: credited,
+                categories: BTreeMap::from([(category, credited)]),
+                quest_completed: 1,
+                date_last_quest: env::block_timestamp()
+            };
+
+            let new_token = TokenMetadata {
+                title: Some(POR_TITLE.to_string()),
+                description: Some("This nft contains the information with the progress within the network".to_string()),
+                media: Some(IMAGE.to_string()),
+                expires_at: None,
+                starts_at: None,
+                copies: None,
+                extra: Some(to_extra(&por)),
+                issued_at: None,
+                media_hash: None,
+                reference: None,
+                reference_hash: None,
+                updated_at: None
+            };
+
+            let token_id = self.internal_next_token_id();
+            self.internal_mint(&token_id, new_token, receiver_id, HashMap::new());
+            token_ids.push(token_id);
+            por
+        };
+
+        self.internal_record_reputation_change(
+            receiver_id,
+            ReputationReason::QuestCompleted,
+            Some(quest_id),
+            por.score - credited,
+            por.score,
         );
-        match env::promise_result(0) {
-            PromiseResult::NotReady => unreachable!(),
-            PromiseResult::Failed => {
-                return "Error al obtener el valor".to_string();
-            },
-            PromiseResult::Successful(result) => {
-                let value = std::str::from_utf8(&result).unwrap();
-                let receiver_id = env::signer_account_id();
-                let initial_storage_usage = env::storage_usage();
-                if value != "0" {
-                    let mut new_token = TokenMetadata {
-                        title:  Some(name.to_string()), 
-                        description:  Some(description.to_string()),
-                        media:  Some(image.to_string()),
-                        expires_at: None,
-                        starts_at: None,
-                        copies: None,
-                        extra: None,
-                        issued_at: None,
-                        media_hash: None,
-                        reference: None,
-                        reference_hash: None,
-                        updated_at: None
-                    };
-                
-                        let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                
-                        let mut empty_quest = Quest {
-                            score : 0,
-                            date: 0
-                        };
-                
-                        empty_quest.score = score;
-                        empty_quest.date = env::block_timestamp();
-                
-                        let mut extra_data_string = serde_json::to_string(&empty_quest).unwrap();
-                        extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                        new_token.extra = Some(extra_data_string);
-                
-                        let mut royalty = HashMap::new();
-                
-                        let token = Token {
-                            owner_id: receiver_id.clone(),
-                            approved_account_ids: Default::default(),
-                            next_approval_id: 0,
-                            royalty
-                        };
-                
-                        assert!(
-                            self.tokens_by_id.insert(&token_id, &token).is_none(),
-                            "Token already exists"
-                        );
-                
-                        self.token_metadata_by_id.insert(&token_id, &new_token);
-                        self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                
-                        if has_por_nft {
-                            let tokens_for_owner_set = self.tokens_per_owner.get(&receiver_id);
-                            let tokens = if let Some(tokens_for_owner_set) = tokens_for_owner_set {
-                                tokens_for_owner_set
-                            } else {
-                                near_sdk::collections::UnorderedSet::new(b"tokens".to_vec()) 
-                            };                
-                            let start = u128::from(U128(0));
-                            let mut por_nft = tokens.iter()
-                            .skip(start as usize) 
-                            .filter_map(|token_id| {
-                                let token = self.nft_token(token_id.clone()).unwrap();
-                                let title = token.metadata.title.unwrap_or_default();
-                                if title == "Proof Of Reputation NFT".to_string() {
-                                    Some(self.nft_token(token_id.clone()).unwrap())
-                                } else {
-                                    None
-                                }
-                            })
-                            .next().unwrap();
-                
-                            let mut metadata = self.token_metadata_by_id.get(&por_nft.token_id).unwrap();
-                            let newextradata = str::replace(&metadata.extra.as_ref().unwrap().to_string(), "'", "\"");
-                            let mut extradatajson: PoR = serde_json::from_str(&newextradata).unwrap();
-                
-                            extradatajson.score = extradatajson.score+score;
-                            extradatajson.quest_completed = extradatajson.quest_completed+1;
-                            extradatajson.date_last_quest = env::block_timestamp();
-                
-                            let mut extra_string = serde_json::to_string(&extradatajson).unwrap();
-                            extra_string = str::replace(&extra_string, "\"", "'");
-                            metadata.extra = Some(extra_string.clone());
-                
-                            self.token_metadata_by_id.insert(&por_nft.token_id, &metadata);                
-                        } else {                
-                            let mut new_token = TokenMetadata {
-                                title:  Some("Proof Of Reputation NFT".to_string()), 
-                                description:  Some("This nft contains the information with the progress within the network".to_string()),
-                                media:  Some(IMAGE.to_string()),
-                                expires_at: None,
-                                starts_at: None,
-                                copies: None,
-                                extra: None,
-                                issued_at: None,
-                                media_hash: None,
-                                reference: None,
-                                reference_hash: None,
-                                updated_at: None
-                            };
-                    
-                            let token_id: TokenId = (self.token_metadata_by_id.len()).to_string();
-                    
-                            let mut empty_poh = PoR {
-                                score : 0,
-                                quest_completed : 0,
-                                date_last_quest : 0
-                            };
-                    
-                            empty_poh.score = score;
-                            empty_poh.quest_completed = 1;
-                            empty_poh.date_last_quest = env::block_timestamp();
-                    
-                            let mut extra_data_string = serde_json::to_string(&empty_poh).unwrap();
-                            extra_data_string = str::replace(&extra_data_string, "\"", "'");
-                            new_token.extra = Some(extra_data_string);
-                    
-                            let mut royalty = HashMap::new();
-                    
-                            let token = Token {
-                                owner_id: receiver_id.clone(),
-                                approved_account_ids: Default::default(),
-                                next_approval_id: 0,
-                                royalty
-                            };
-                    
-                            assert!(
-                                self.tokens_by_id.insert(&token_id, &token).is_none(),
-                                "Token already exists"
-                            );
-                    
-                            self.token_metadata_by_id.insert(&token_id, &new_token);
-                            self.internal_add_token_to_owner(&token.owner_id, &token_id);
-                        }
-                
-                    let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
-                    refund_deposit(required_storage_in_bytes);
-                }
-                return "Success to get Voting Power".to_string();
+
+        // Proving humanity releases the score held until now
+        let mut score_delta = credited;
+        if human_quest {
+            if let Some((released, released_por)) = self.internal_release_pending_score(receiver_id) {
+                score_delta += released;
+                por = released_por;
             }
         }
+
+        self.internal_add_season_score(receiver_id, quest_id, credited);
+
+        if let Some(reward) = config.reward.as_ref() {
+            self.internal_pay_reward(receiver_id, quest_id, reward, held);
+        }
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        match reserved_bytes {
+            Some(reserved_bytes) => self.internal_settle_storage(receiver_id, reserved_bytes, required_storage_in_bytes),
+            None => self.internal_charge_storage(receiver_id, required_storage_in_bytes),
+        }
+
+        ClaimOutcome::Claimed {
+            quest_id,
+            token_ids,
+            score_delta,
+            total_score: por.score,
+            quests_completed: por.quest_completed,
+        }
+    }
+
+    // Check the value returned by the verification call against the threshold of the quest.
+    pub(crate) fn internal_is_eligible(&self, quest_id: u64, account_id: &AccountId, result: &str) -> bool {
+        let quest = self.quests.get(&quest_id).expect("Quest not found");
+        self.internal_observed_value(&quest, account_id, result).is_some_and(|value| value >= quest.threshold())
+    }
+
+    // Get the value observed by the verification call of a quest: the number of valid proofs of humanity for
+    // I-Am-Human, the number of roles of the account for DAO membership or the balance for the rest.
+    // None if the answer can't be parsed.
+    pub(crate) fn internal_observed_value(&self, quest: &QuestConfig, account_id: &AccountId, result: &str) -> Option<u128> {
+        match &quest.verifier {
+            QuestVerifier::ImHuman => self.internal_human_proofs(result).map(|proofs| proofs.len() as u128),
+            QuestVerifier::DaoMember { roles, .. } => dao_roles_of(result, account_id, roles),
+            _ => observed_value(result),
+        }
+    }
+
+    // Release the storage reserved for a claim that did not mint anything, give back its deposit and log why it failed.
+    pub(crate) fn internal_fail_claim(
+        &mut self,
+        receiver_id: &AccountId,
+        quest_id: u64,
+        error: ClaimError,
+        claim: &PendingClaim,
+    ) -> ClaimOutcome {
+        self.internal_settle_storage(receiver_id, claim.reserved_bytes, 0);
+        self.internal_refund_storage_deposit(receiver_id, claim.deposit.0, &claim.payer);
+        self.internal_log_claim_failed(receiver_id, quest_id, error)
+    }
+
+    // Log the quest_claim_failed event of a claim and build its outcome.
+    pub(crate) fn internal_log_claim_failed(&self, receiver_id: &AccountId, quest_id: u64, error: ClaimError) -> ClaimOutcome {
+        let quest_claim_failed_log: EventLog = EventLog {
+            standard: POR_STANDARD_NAME.to_string(),
+            version: POR_EVENT_VERSION.to_string(),
+            event: EventLogVariant::QuestClaimFailed(vec![QuestClaimFailedLog {
+                account_id: receiver_id.to_string(),
+                quest_id,
+                error: error.clone(),
+            }]),
+        };
+        env::log_str(&quest_claim_failed_log.to_string());
+
+        ClaimOutcome::Failed { quest_id, error }
     }
-}
\ No newline at end of file
+}
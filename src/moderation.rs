@@ -0,0 +1,70 @@
+use crate::*;
+
+#[near_bindgen]
+impl Contract {
+    //add an account allowed to revoke fraudulent quests, only the owner can call it
+    pub fn add_moderator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        self.moderators.insert(&account_id);
+    }
+
+    pub fn remove_moderator(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        self.moderators.remove(&account_id);
+    }
+
+    pub fn get_moderators(&self) -> Vec<AccountId> {
+        self.moderators.to_vec()
+    }
+
+    //change the account that receives the slashed bonds, only the owner can call it
+    pub fn set_treasury(&mut self, account_id: AccountId) {
+        self.assert_owner();
+        self.treasury_id = account_id;
+    }
+
+    pub fn get_treasury(&self) -> AccountId {
+        self.treasury_id.clone()
+    }
+
+    // Method for moderators to revoke a quest obtained by fraud. The quest token is burned (wherever it is now), the completion
+    // is no longer counted in the supply of the quest and its score is taken from the Proof of Reputation of the account and from
+    // the seasons that are not closed (the score and reward held as pending are taken back), the vouches made by the account are revoked and its bond
+    // (including the amounts in the unbonding queue) is slashed to the treasury. Returns the amount slashed
+    pub fn revoke_quest(&mut self, account_id: AccountId, quest_id: u64) -> U128 {
+        let moderator_id = env::predecessor_account_id();
+        assert!(self.moderators.contains(&moderator_id), "Only moderators can revoke quests");
+        let (token_id, quest) = self
+            .internal_quest_badge_of(&account_id, quest_id)
+            .expect("The account has not completed this quest");
+        let config = self.quests.get(&quest_id);
+        let category = config.as_ref().map(|config| config.category);
+        if config.is_some_and(|config| config.verifier == QuestVerifier::ImHuman) {
+            self.verified_humans.remove(&account_id);
+        }
+
+        self.internal_burn(&token_id, &moderator_id, Some("Quest revoked".to_string()));
+        if let Some(completion) = self.internal_remove_quest_completion(&account_id, quest_id) {
+            self.internal_take_season_scores(&account_id, &completion);
+        }
+        let completions = self.quest_completions.get(&quest_id).unwrap_or(0);
+        self.quest_completions.insert(&quest_id, &completions.saturating_sub(1));
+        let credited = quest.score.saturating_sub(self.internal_take_pending_score(&account_id, quest_id));
+        self.internal_take_pending_rewards(&account_id, quest_id);
+        self.internal_update_por(&account_id, ReputationReason::QuestRevoked, Some(quest_id), |por| {
+            por.score = por.score.saturating_sub(credited);
+            if let Some(score) = category.and_then(|category| por.categories.get_mut(&category)) {
+                *score = score.saturating_sub(credited);
+            }
+            por.quest_completed = por.quest_completed.saturating_sub(1);
+        })
+        .expect("The account has no reputation");
+        self.internal_revoke_vouches_by(&account_id);
+
+        let slashed = self.internal_slash_bond(&account_id);
+        if slashed > 0 {
+            Promise::new(self.treasury_id.clone()).transfer(slashed);
+        }
+        U128(slashed)
+    }
+}
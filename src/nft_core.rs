@@ -48,9 +48,10 @@ trait NonFungibleTokenReceiver {
 
 #[ext_contract(ext_nft)]
 pub trait ExternsContract {
-    fn is_human(&self, account: String) -> String;
+    fn sbt_tokens_by_owner(&self, account: String, issuer: Option<String>, from_class: Option<u64>, limit: Option<u32>, with_expired: Option<bool>) -> String;
     fn ft_balance_of(&self, account_id: String) -> u128;
     fn get_locked_balance(&self, voter_id: String) -> u128;
+    fn get_policy(&self) -> String;
 }
 
 #[ext_contract(ext_self)]
@@ -65,10 +66,12 @@ trait NonFungibleTokenResolver {
         memo: Option<String>,
     ) -> bool;
 
-    fn resolve_mint_im_human(&self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String;
-    fn resolve_mint_st_near(&self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String;
-    fn resolve_mint_meta_token(&self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String;
-    fn resolve_mint_voting_power(&self, name: String, description: String, score: u64, image: String, has_por_nft: bool) -> String;
+    fn resolve_mint_im_human(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome;
+    fn resolve_mint_st_near(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome;
+    fn resolve_mint_meta_token(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome;
+    fn resolve_mint_voting_power(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome;
+    fn resolve_mint_dao_member(&mut self, quest_id: u64, account_id: AccountId, nonce: u64) -> ClaimOutcome;
+    fn resolve_eligibility(&self, quest_id: u64, account_id: AccountId, missing_prerequisites: Vec<u64>, error: Option<ClaimError>) -> EligibilityReport;
 }
 
 /*
@@ -76,7 +79,7 @@ trait NonFungibleTokenResolver {
     this is stored on THIS contract and is meant to analyze what happened in the cross contract call when nft_on_transfer was called
     as part of the nft_transfer_call method
 */ 
-trait NonFungibleTokenResolver {
+pub trait NonFungibleTokenResolver {
     fn nft_resolve_transfer(
         &mut self,
         //we introduce an authorized ID for logging the transfer event
@@ -0,0 +1,107 @@
+use crate::*;
+use ed25519_dalek::Verifier;
+use near_sdk::CurveType;
+
+//fixed prefix of the messages signed by the oracles, so a signature made for something else can't be used as a claim
+pub const SIGNED_CLAIM_PREFIX: &[u8] = b"por:signed_claim:";
+
+//the claim signed by an oracle for a quest verified off-chain (GitHub contributions, Discord roles...)
+//the oracle signs the prefix, the account of this contract and the borsh serialization of this struct with its ed25519 key
+#[derive(BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SignedClaim {
+    pub account_id: AccountId,
+    pub quest_id: u64,
+    //used once per account to block replays of the same claim
+    pub nonce: U64,
+    //block timestamp (in nanoseconds) after which the claim can't be used
+    pub expires_at: U64,
+}
+
+impl SignedClaim {
+    //get the message signed by the oracle for the claim on the passed in contract, so the same claim can't be
+    //used on another deployment of the contract that trusts the same oracle key
+    pub fn message(&self, contract_id: &AccountId) -> Vec<u8> {
+        [SIGNED_CLAIM_PREFIX.to_vec(), contract_id.try_to_vec().unwrap(), self.try_to_vec().unwrap()].concat()
+    }
+}
+
+//check the ed25519 signature of a message against the passed in public key
+pub(crate) fn verify_signature(public_key: &PublicKey, message: &[u8], signature: &[u8]) -> bool {
+    if public_key.curve_type() != CurveType::ED25519 {
+        return false;
+    }
+    //the first byte of the key is the curve type
+    let public_key = match ed25519_dalek::PublicKey::from_bytes(&public_key.as_bytes()[1..]) {
+        Ok(public_key) => public_key,
+        Err(_) => return false,
+    };
+    let signature = match ed25519_dalek::Signature::try_from(signature) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+    public_key.verify(message, &signature).is_ok()
+}
+
+#[near_bindgen]
+impl Contract {
+    //register a public key of an oracle allowed to sign quest claims, only the governance account (or the owner if there is none) can call it
+    pub fn add_oracle_key(&mut self, public_key: PublicKey) {
+        self.assert_governance();
+        assert_eq!(public_key.curve_type(), CurveType::ED25519, "Only ed25519 keys are supported");
+        self.oracle_keys.insert(&public_key);
+    }
+
+    //remove a public key of an oracle, only the governance account (or the owner if there is none) can call it
+    pub fn remove_oracle_key(&mut self, public_key: PublicKey) {
+        self.assert_governance();
+        assert!(self.oracle_keys.remove(&public_key), "Oracle key not found");
+    }
+
+    //get the public keys of the oracles
+    pub fn get_oracle_keys(&self) -> Vec<PublicKey> {
+        self.oracle_keys.to_vec()
+    }
+
+    //check if the nonce of a signed claim was already used by the account
+    pub fn is_nonce_used(&self, account_id: AccountId, nonce: U64) -> bool {
+        self.used_nonces.contains(&(account_id, nonce.0))
+    }
+
+    // Method to claim a quest with a payload signed by one of the oracles, no cross contract call is needed.
+    // The attached deposit is added to the storage balance of the account and the quest is minted
+    // the same way as the quests verified with a cross contract call.
+    #[payable]
+    pub fn claim_signed_quest(&mut self, claim: SignedClaim, public_key: PublicKey, signature: Base64VecU8) -> ClaimOutcome {
+        self.assert_tokens_migrated();
+        let account_id = env::predecessor_account_id();
+        assert_eq!(claim.account_id, account_id, "The claim was signed for another account");
+        assert!(claim.expires_at.0 > env::block_timestamp(), "The claim has expired");
+
+        let quest = self.internal_get_active_quest(claim.quest_id);
+        assert_eq!(quest.verifier, QuestVerifier::Oracle, "This quest can't be claimed with a signature");
+        assert!(self.oracle_keys.contains(&public_key), "Unknown oracle key");
+        assert!(
+            verify_signature(&public_key, &claim.message(&env::current_account_id()), &signature.0),
+            "Invalid signature"
+        );
+        assert!(
+            !self.used_nonces.contains(&(account_id.clone(), claim.nonce.0)),
+            "The nonce has already been used"
+        );
+        self.internal_assert_can_complete(&account_id, claim.quest_id, &quest);
+
+        // Keep the deposit as storage balance of the account, the storage of the nonce and the mint is charged against it
+        let deposit = env::attached_deposit();
+        if deposit > 0 {
+            self.internal_storage_deposit(&account_id, deposit);
+        }
+
+        // Mark the nonce as used so the same claim can't be replayed
+        let initial_storage_usage = env::storage_usage();
+        self.used_nonces.insert(&(account_id.clone(), claim.nonce.0));
+        self.internal_charge_storage(&account_id, env::storage_usage() - initial_storage_usage);
+
+        self.internal_mint_quest(&account_id, claim.quest_id, QuestBadge::from_quest(&quest), None)
+    }
+}
@@ -0,0 +1,96 @@
+use crate::*;
+
+//time (in nanoseconds) after which a pending claim whose callback never cleared it stops blocking new claims
+pub const PENDING_CLAIM_TIMEOUT: u64 = 10 * 60 * 1_000_000_000;
+
+//a claim started with nft_mint_quest that is waiting for its verification call
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingClaim {
+    pub quest_id: u64,
+    //passed in to the callback of the claim, which only clears the claim with the same nonce
+    pub nonce: u64,
+    //block timestamp (in nanoseconds) when the claim started
+    pub started_at: U64,
+    //deposit attached to the claim and the account that attached it, it is given back to that account if the claim fails
+    pub deposit: U128,
+    pub payer: AccountId,
+    //bytes of the storage balance of the account reserved for the claim until its callback runs
+    pub reserved_bytes: u64,
+}
+
+impl PendingClaim {
+    fn is_expired(&self) -> bool {
+        env::block_timestamp() > self.started_at.0 + PENDING_CLAIM_TIMEOUT
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    //get the claims of the account that are waiting for their verification call
+    pub fn get_pending_claims(&self, account_id: AccountId) -> Vec<PendingClaim> {
+        self.pending_claims
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|claim| !claim.is_expired())
+            .collect()
+    }
+}
+
+impl Contract {
+    //check if the account has a claim of the quest waiting for its verification call
+    pub(crate) fn internal_is_claim_pending(&self, account_id: &AccountId, quest_id: u64) -> bool {
+        self.pending_claims
+            .get(account_id)
+            .map(|claims| claims.iter().any(|claim| claim.quest_id == quest_id && !claim.is_expired()))
+            .unwrap_or(false)
+    }
+
+    //register a claim that is waiting for its verification call and return its nonce. The expired claims of the
+    //account are dropped and the storage they reserved is released
+    pub(crate) fn internal_add_pending_claim(
+        &mut self,
+        account_id: &AccountId,
+        quest_id: u64,
+        deposit: Balance,
+        payer: AccountId,
+        reserved_bytes: u64,
+    ) -> u64 {
+        let (mut claims, expired): (Vec<PendingClaim>, Vec<PendingClaim>) = self
+            .pending_claims
+            .get(account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|claim| claim.quest_id != quest_id && !claim.is_expired());
+        let expired_bytes = expired.iter().map(|claim| claim.reserved_bytes).sum();
+        self.internal_settle_storage(account_id, expired_bytes, 0);
+
+        let nonce = self.next_claim_nonce;
+        self.next_claim_nonce += 1;
+        claims.push(PendingClaim {
+            quest_id,
+            nonce,
+            started_at: U64(env::block_timestamp()),
+            deposit: U128(deposit),
+            payer,
+            reserved_bytes,
+        });
+        self.pending_claims.insert(account_id, &claims);
+        nonce
+    }
+
+    //clear a claim once its callback runs, whatever its result, and return it. Only the claim with the same quest and
+    //nonce is cleared: the callback of a claim that expired and was replaced must not clear the newer claim
+    pub(crate) fn internal_remove_pending_claim(&mut self, account_id: &AccountId, quest_id: u64, nonce: u64) -> Option<PendingClaim> {
+        let mut claims = self.pending_claims.get(account_id)?;
+        let index = claims.iter().position(|claim| claim.quest_id == quest_id && claim.nonce == nonce)?;
+        let claim = claims.remove(index);
+        if claims.is_empty() {
+            self.pending_claims.remove(account_id);
+        } else {
+            self.pending_claims.insert(account_id, &claims);
+        }
+        Some(claim)
+    }
+}
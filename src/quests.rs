@@ -0,0 +1,446 @@
+use crate::*;
+
+//how the completion of a quest is verified
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum QuestVerifier {
+    //SBTs of the account in the I-Am-Human registry
+    ImHuman,
+    //stNEAR balance in Meta Pool
+    StNear,
+    //META token balance
+    MetaToken,
+    //locked balance in Meta Vote
+    VotingPower,
+    //awarded by a trusted issuer with award_quest (off-chain quests)
+    Issuer,
+    //claimed with a payload signed by a registered oracle key (claim_signed_quest)
+    Oracle,
+    //member of a group role in the policy (get_policy) of a Sputnik/AstroDAO, of any role if roles is empty
+    DaoMember {
+        dao_id: AccountId,
+        #[serde(default)]
+        roles: Vec<String>,
+    },
+}
+
+//the dimension of the reputation a quest adds score to
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "lowercase")]
+pub enum QuestCategory {
+    Identity,
+    DeFi,
+    Governance,
+    Learning,
+}
+
+impl QuestCategory {
+    pub const ALL: [QuestCategory; 4] = [
+        QuestCategory::Identity,
+        QuestCategory::DeFi,
+        QuestCategory::Governance,
+        QuestCategory::Learning,
+    ];
+}
+
+//bonus points for the first accounts that complete a quest
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EarlyBird {
+    //number of completers that get the bonus
+    pub completers: u64,
+    pub bonus: u64,
+}
+
+//score multiplier (basis points, 10000 = 1x) applied from a streak length
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StreakMultiplier {
+    pub min_streak: u64,
+    pub multiplier: u32,
+}
+
+//how a repeatable quest can be completed again
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RepeatConfig {
+    //minimum time (in nanoseconds) between two completions
+    pub cooldown: U64,
+    //maximum time (in nanoseconds) between two completions to keep the streak, the streak resets after it
+    pub streak_window: U64,
+    //the multiplier with the highest min_streak reached is applied to the score of the quest
+    #[serde(default)]
+    pub streak_multipliers: Vec<StreakMultiplier>,
+}
+
+impl RepeatConfig {
+    //get the score of a completion with the passed in streak
+    pub(crate) fn apply_multiplier(&self, score: u64, streak: u64) -> u64 {
+        let multiplier = self
+            .streak_multipliers
+            .iter()
+            .filter(|streak_multiplier| streak_multiplier.min_streak <= streak)
+            .max_by_key(|streak_multiplier| streak_multiplier.min_streak)
+            .map(|streak_multiplier| streak_multiplier.multiplier)
+            .unwrap_or(DEFAULT_CATEGORY_WEIGHT);
+        (score as u128 * multiplier as u128 / 10_000) as u64
+    }
+}
+
+//a quest that can be completed to earn reputation
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestConfig {
+    pub name: String,
+    pub description: String,
+    //score added to the Proof of Reputation when the quest is completed
+    pub score: u64,
+    //IPFS CID of the image of the quest token
+    pub image: String,
+    pub verifier: QuestVerifier,
+    //minimum value the verification call must return (number of SBTs or balance), 1 if not set
+    #[serde(default)]
+    pub min_value: Option<U128>,
+    pub category: QuestCategory,
+    //quests that must be completed before this one can be claimed
+    #[serde(default)]
+    pub prerequisites: Vec<u64>,
+    //maximum number of accounts that can complete the quest, None for no limit
+    #[serde(default)]
+    pub max_completions: Option<u64>,
+    #[serde(default)]
+    pub early_bird: Option<EarlyBird>,
+    //repeatable quests can be completed again once the cooldown has finished
+    #[serde(default)]
+    pub repeat: Option<RepeatConfig>,
+    //NEP-141 tokens paid on each completion, from the reward pool of the quest
+    #[serde(default)]
+    pub reward: Option<QuestReward>,
+    //inactive quests can't be claimed or awarded
+    pub active: bool,
+}
+
+//whether an account can claim a quest
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum QuestStatus {
+    Completed,
+    Claimable,
+    //some prerequisites are missing
+    Locked,
+    //the quest belongs to seasons and none of them is open
+    OutOfSeason,
+    //the quest has reached its maximum number of completions
+    SupplyExhausted,
+}
+
+//the availability of a quest for an account
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestAvailability {
+    pub quest_id: u64,
+    pub name: String,
+    pub status: QuestStatus,
+    //prerequisites the account still has to complete
+    pub missing_prerequisites: Vec<u64>,
+}
+
+//how many times a quest has been completed and how many completions are left
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestSupply {
+    pub completions: u64,
+    pub max_completions: Option<u64>,
+    //None if the quest has no limit
+    pub remaining: Option<u64>,
+}
+
+//the progress of an account in a repeatable quest
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestProgress {
+    pub token_id: TokenId,
+    pub times_completed: u64,
+    //0 if the streak window was missed
+    pub streak: u64,
+    pub last_completed: U64,
+    //when the quest can be completed again, None if the quest is not repeatable
+    pub next_claim_at: Option<U64>,
+}
+
+impl QuestConfig {
+    //minimum value the verification call must return to complete the quest
+    pub(crate) fn threshold(&self) -> u128 {
+        self.min_value.map(|value| value.0).unwrap_or(1)
+    }
+}
+
+//the quest as returned from the view calls
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonQuest {
+    pub quest_id: u64,
+    #[serde(flatten)]
+    pub quest: QuestConfig,
+}
+
+//the quests the contract starts with
+pub(crate) fn default_quests() -> Vec<(u64, QuestConfig)> {
+    let quest = |name: &str, description: &str, score: u64, image: &str, verifier: QuestVerifier, category: QuestCategory, prerequisites: Vec<u64>| QuestConfig {
+        name: name.to_string(),
+        description: description.to_string(),
+        score,
+        image: image.to_string(),
+        verifier,
+        min_value: None,
+        category,
+        prerequisites,
+        max_completions: None,
+        early_bird: None,
+        repeat: None,
+        reward: None,
+        active: true,
+    };
+    vec![
+        (0, quest("I'm Human", "Identify yourself as a human in the I am human app.", 40, "QmSt8ngyrTE6JG5gwPRNsHK3VkVMv5MZf4z2d8usGoCgNg", QuestVerifier::ImHuman, QuestCategory::Identity, vec![])),
+        (1, quest("stNEAR", "Stake NEAR in metapool to get stNEAR.", 15, "QmPKTBiKzuFg4G62hDtJuhuqwrrT2MbgWSLWX9LMxeTWQn", QuestVerifier::StNear, QuestCategory::DeFi, vec![0])),
+        (2, quest("Meta Token", "Get META token in your account.", 15, "Qmd8kZkFhFRimwSEuHa6Qyi91E1RxCU65jdunDmEYFNYcY", QuestVerifier::MetaToken, QuestCategory::DeFi, vec![])),
+        (3, quest("Voting Power", "You are registered in I am human", 30, "QmZ17TnjJZEGDn1ZpjTr7cMivdxBAPWFuJhpTsm1sZr3LE", QuestVerifier::VotingPower, QuestCategory::Governance, vec![0])),
+    ]
+}
+
+#[near_bindgen]
+impl Contract {
+    //add a new quest to the registry, only the governance account (or the owner if there is none) can call it
+    pub fn add_quest(&mut self, quest_id: u64, quest: QuestConfig) {
+        self.assert_governance();
+        self.internal_assert_valid_quest(quest_id, &quest);
+        assert!(
+            self.quests.insert(&quest_id, &quest).is_none(),
+            "Quest already exists"
+        );
+    }
+
+    //replace the configuration of an existing quest, only the governance account (or the owner if there is none) can call it
+    pub fn update_quest(&mut self, quest_id: u64, quest: QuestConfig) {
+        self.assert_governance();
+        self.internal_assert_valid_quest(quest_id, &quest);
+        //the pool of the quest holds tokens of its current reward, they have to be withdrawn before changing it
+        let token_id = |quest: &QuestConfig| quest.reward.as_ref().map(|reward| reward.token_id.clone());
+        assert!(
+            self.reward_pools.get(&quest_id).unwrap_or(0) == 0
+                || self.quests.get(&quest_id).is_some_and(|previous| token_id(&previous) == token_id(&quest)),
+            "Withdraw the reward pool of the quest before changing its reward token"
+        );
+        assert!(
+            self.quests.insert(&quest_id, &quest).is_some(),
+            "Quest not found"
+        );
+    }
+
+    //activate or deactivate a quest, only the governance account (or the owner if there is none) can call it
+    pub fn set_quest_active(&mut self, quest_id: u64, active: bool) {
+        self.assert_governance();
+        let mut quest = self.quests.get(&quest_id).expect("Quest not found");
+        quest.active = active;
+        self.quests.insert(&quest_id, &quest);
+    }
+
+    //get the configuration of a quest
+    pub fn get_quest(&self, quest_id: u64) -> Option<JsonQuest> {
+        self.quests
+            .get(&quest_id)
+            .map(|quest| JsonQuest { quest_id, quest })
+    }
+
+    //get the active quests (and the completed ones) with their status for the passed in account
+    pub fn get_available_quests(&self, account_id: AccountId) -> Vec<QuestAvailability> {
+        let completed = self.internal_completed_quests(&account_id);
+
+        self.quests.iter()
+            .filter(|(quest_id, quest)| quest.active || completed.contains(quest_id))
+            .map(|(quest_id, quest)| {
+                let missing_prerequisites = self.internal_missing_prerequisites(&completed, &quest);
+                //repeatable quests are claimable again once their cooldown has finished
+                let status = if completed.contains(&quest_id)
+                    && (!quest.active || self.internal_claim_error(&account_id, quest_id, &quest).is_some())
+                {
+                    QuestStatus::Completed
+                } else if !completed.contains(&quest_id) && !self.internal_has_supply(quest_id, &quest) {
+                    QuestStatus::SupplyExhausted
+                } else if !missing_prerequisites.is_empty() {
+                    QuestStatus::Locked
+                } else if !self.internal_is_in_season(quest_id) {
+                    QuestStatus::OutOfSeason
+                } else {
+                    QuestStatus::Claimable
+                };
+                QuestAvailability { quest_id, name: quest.name, status, missing_prerequisites }
+            })
+            .collect()
+    }
+
+    //get the number of completions and the streak of the account in a quest
+    pub fn get_quest_progress(&self, account_id: AccountId, quest_id: u64) -> Option<QuestProgress> {
+        let config = self.quests.get(&quest_id)?;
+        let (token_id, quest) = self.internal_quest_badge_of(&account_id, quest_id)?;
+        let streak = match config.repeat.as_ref() {
+            Some(repeat) if env::block_timestamp() > quest.date + repeat.streak_window.0 => 0,
+            _ => quest.streak.unwrap_or(1),
+        };
+        Some(QuestProgress {
+            token_id,
+            times_completed: quest.times_completed.unwrap_or(1),
+            streak,
+            last_completed: U64(quest.date),
+            next_claim_at: config.repeat.map(|repeat| U64(quest.date + repeat.cooldown.0)),
+        })
+    }
+
+    //get the number of completions of a quest and how many are left
+    pub fn get_quest_supply(&self, quest_id: u64) -> QuestSupply {
+        let quest = self.quests.get(&quest_id).expect("Quest not found");
+        let completions = self.quest_completions.get(&quest_id).unwrap_or(0);
+        QuestSupply {
+            completions,
+            max_completions: quest.max_completions,
+            remaining: quest.max_completions.map(|max| max.saturating_sub(completions)),
+        }
+    }
+
+    //get the quests in the registry using pagination
+    pub fn get_quests(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonQuest> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.quests.iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(quest_id, quest)| JsonQuest { quest_id, quest })
+            .collect()
+    }
+}
+
+impl Contract {
+    //get a quest that can be completed right now, panics with the typed claim error otherwise
+    pub(crate) fn internal_get_active_quest(&self, quest_id: u64) -> QuestConfig {
+        let quest = self
+            .quests
+            .get(&quest_id)
+            .unwrap_or_else(|| panic!("{}", ClaimError::QuestNotFound));
+        assert!(quest.active, "{}", ClaimError::QuestInactive);
+        assert!(self.internal_is_in_season(quest_id), "{}", ClaimError::OutOfSeason);
+        quest
+    }
+
+    //get a quest that the account can claim right now, or the typed reason why it can't
+    pub(crate) fn internal_claimable_quest(&self, account_id: &AccountId, quest_id: u64) -> Result<QuestConfig, ClaimError> {
+        let quest = self.quests.get(&quest_id).ok_or(ClaimError::QuestNotFound)?;
+        if !quest.active {
+            return Err(ClaimError::QuestInactive);
+        }
+        if !self.internal_is_in_season(quest_id) {
+            return Err(ClaimError::OutOfSeason);
+        }
+        match self.internal_claim_error(account_id, quest_id, &quest) {
+            Some(error) => Err(error),
+            None => Ok(quest),
+        }
+    }
+
+    //check if the quest can still be completed by another account
+    pub(crate) fn internal_has_supply(&self, quest_id: u64, quest: &QuestConfig) -> bool {
+        match quest.max_completions {
+            Some(max) => self.quest_completions.get(&quest_id).unwrap_or(0) < max,
+            None => true,
+        }
+    }
+
+    //get the reason why the account can't complete the quest right now, None if it can
+    pub(crate) fn internal_claim_error(&self, account_id: &AccountId, quest_id: u64, quest: &QuestConfig) -> Option<ClaimError> {
+        if self.internal_is_claim_pending(account_id, quest_id) {
+            return Some(ClaimError::ClaimPending);
+        }
+        match self.internal_quest_badge_of(account_id, quest_id) {
+            Some((_, badge)) => match quest.repeat.as_ref() {
+                None => Some(ClaimError::AlreadyClaimed),
+                Some(repeat) if env::block_timestamp() < badge.date + repeat.cooldown.0 => Some(ClaimError::CooldownActive),
+                Some(_) => None,
+            },
+            None => {
+                let completed = self.internal_completed_quests(account_id);
+                if !self.internal_has_supply(quest_id, quest) {
+                    Some(ClaimError::SupplyExhausted)
+                } else if !self.internal_missing_prerequisites(&completed, quest).is_empty() {
+                    Some(ClaimError::PrerequisitesMissing)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    //get the prerequisites of the quest that are not in the passed in completed quests
+    pub(crate) fn internal_missing_prerequisites(&self, completed: &[u64], quest: &QuestConfig) -> Vec<u64> {
+        quest
+            .prerequisites
+            .iter()
+            .filter(|prerequisite| !completed.contains(prerequisite))
+            .copied()
+            .collect()
+    }
+
+    //make sure that the account can complete the quest right now
+    pub(crate) fn internal_assert_can_complete(&self, account_id: &AccountId, quest_id: u64, quest: &QuestConfig) {
+        if let Some(error) = self.internal_claim_error(account_id, quest_id, quest) {
+            panic!("{}", error);
+        }
+    }
+
+    //make sure that the repeat settings are consistent and the prerequisites of a quest exist and don't include the quest itself
+    fn internal_assert_valid_quest(&self, quest_id: u64, quest: &QuestConfig) {
+        if let Some(repeat) = quest.repeat.as_ref() {
+            assert!(
+                repeat.streak_window.0 >= repeat.cooldown.0,
+                "The streak window can't be shorter than the cooldown"
+            );
+        }
+        if let Some(reward) = quest.reward.as_ref() {
+            assert!(reward.amount.0 > 0, "The reward amount can't be 0");
+            assert!(reward.budget.0 >= reward.amount.0, "The reward budget can't be smaller than the reward amount");
+        }
+        for prerequisite in quest.prerequisites.iter() {
+            assert_ne!(*prerequisite, quest_id, "A quest can't be its own prerequisite");
+            assert!(
+                self.quests.get(prerequisite).is_some(),
+                "Prerequisite {} not found",
+                prerequisite
+            );
+        }
+
+        //walk the prerequisites of the prerequisites, if the quest is reached it could never be claimed
+        let mut visited = std::collections::BTreeSet::new();
+        let mut to_visit = quest.prerequisites.clone();
+        while let Some(prerequisite) = to_visit.pop() {
+            assert_ne!(prerequisite, quest_id, "The prerequisites of a quest can't form a cycle");
+            if visited.insert(prerequisite) {
+                if let Some(config) = self.quests.get(&prerequisite) {
+                    to_visit.extend(config.prerequisites);
+                }
+            }
+        }
+    }
+
+    //get the id of the quest that has the passed in name
+    pub(crate) fn internal_quest_id_by_name(&self, name: &str) -> Option<u64> {
+        self.quests
+            .iter()
+            .find(|(_, quest)| quest.name == name)
+            .map(|(quest_id, _)| quest_id)
+    }
+}
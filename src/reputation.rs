@@ -0,0 +1,272 @@
+use crate::*;
+
+//the maximum number of accounts that can be queried at once with get_reputations
+const MAX_ACCOUNTS_PER_QUERY: usize = 100;
+//weight of a category in the composite score when the owner hasn't set one (basis points, 10000 = 1x)
+pub const DEFAULT_CATEGORY_WEIGHT: u32 = 10_000;
+
+//reputation tiers given by the score of the account
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum ReputationTier {
+    //the account has no reputation yet
+    None,
+    Bronze,
+    Silver,
+    Gold,
+}
+
+impl ReputationTier {
+    //get the tier that corresponds to the passed in score
+    pub fn from_score(score: u64) -> Self {
+        match score {
+            0 => ReputationTier::None,
+            1..=49 => ReputationTier::Bronze,
+            50..=99 => ReputationTier::Silver,
+            _ => ReputationTier::Gold,
+        }
+    }
+}
+
+//the reputation of an account as returned to other contracts and frontends
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReputationView {
+    pub account_id: AccountId,
+    //total score of the account
+    pub score: u64,
+    //score earned in each category
+    pub categories: BTreeMap<QuestCategory, u64>,
+    //sum of the category scores weighted with the category weights
+    pub composite_score: u64,
+    //number of quests completed by the account
+    pub quests_completed: u64,
+    //ids of the quests completed by the account
+    pub quests: Vec<u64>,
+    pub tier: ReputationTier,
+    //score held until the account proves it is human, under the human gating policy
+    pub pending_score: u64,
+    //block timestamp (in nanoseconds) of the last quest completed, None if the account has no reputation
+    pub last_activity: Option<U64>,
+    //ID of the Proof of Reputation token of the account
+    pub por_token_id: Option<TokenId>,
+}
+
+//completion of a quest by an account
+#[derive(BorshDeserialize, BorshSerialize, Clone, Debug, PartialEq)]
+pub struct QuestCompletion {
+    //badge minted for the completion, repeated completions update it wherever it is now
+    pub token_id: TokenId,
+    //score added to each season by the completions of the quest, taken back if the quest is revoked
+    pub seasons: BTreeMap<u64, u64>,
+}
+
+impl PoR {
+    //take points from the score. The score earned outside the categories (bonds, vouches, reputations created before
+    //the categories) goes first, the rest is taken from the categories in proportion to their score, so the composite
+    //score follows the total score
+    pub(crate) fn deduct(&mut self, points: u64) {
+        self.score = self.score.saturating_sub(points);
+        let categorized: u64 = self.categories.values().sum();
+        if categorized <= self.score {
+            return;
+        }
+
+        let mut excess = categorized - self.score;
+        for score in self.categories.values_mut() {
+            let cut = std::cmp::min(
+                excess,
+                ((*score as u128 * (categorized - self.score) as u128).div_ceil(categorized as u128)) as u64,
+            );
+            *score -= cut;
+            excess -= cut;
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    //get the reputation of the passed in account
+    pub fn get_reputation(&self, account_id: AccountId) -> ReputationView {
+        let por = self.internal_get_por(&account_id);
+        let score = por.as_ref().map(|(_, por)| por.score).unwrap_or(0);
+
+        ReputationView {
+            score,
+            categories: por.as_ref().map(|(_, por)| por.categories.clone()).unwrap_or_default(),
+            composite_score: por.as_ref().map(|(_, por)| self.internal_composite_score(por)).unwrap_or(0),
+            quests_completed: por.as_ref().map(|(_, por)| por.quest_completed).unwrap_or(0),
+            quests: self.internal_completed_quests(&account_id),
+            tier: ReputationTier::from_score(score),
+            pending_score: self.pending_scores.get(&account_id).map(|pending| pending.values().map(|held| held.score).sum()).unwrap_or(0),
+            last_activity: por.as_ref().map(|(_, por)| U64(por.date_last_quest)),
+            por_token_id: por.map(|(token_id, _)| token_id),
+            account_id,
+        }
+    }
+
+    //get the reputation of several accounts at once, in the same order they were passed in
+    pub fn get_reputations(&self, account_ids: Vec<AccountId>) -> Vec<ReputationView> {
+        assert!(
+            account_ids.len() <= MAX_ACCOUNTS_PER_QUERY,
+            "Cannot query more than {} accounts at once",
+            MAX_ACCOUNTS_PER_QUERY,
+        );
+        account_ids
+            .into_iter()
+            .map(|account_id| self.get_reputation(account_id))
+            .collect()
+    }
+
+    //check if the score of the account is at least the passed in score
+    pub fn has_min_reputation(&self, account_id: AccountId, min_score: u64) -> bool {
+        self.internal_get_por(&account_id)
+            .map(|(_, por)| por.score >= min_score)
+            .unwrap_or(false)
+    }
+
+    //get the score of the account in a single category
+    pub fn get_category_score(&self, account_id: AccountId, category: QuestCategory) -> u64 {
+        self.internal_get_por(&account_id)
+            .and_then(|(_, por)| por.categories.get(&category).copied())
+            .unwrap_or(0)
+    }
+
+    //set the weight of a category in the composite score (basis points), only the governance account (or the owner if there is none) can call it
+    pub fn set_category_weight(&mut self, category: QuestCategory, weight: u32) {
+        self.assert_governance();
+        self.category_weights.insert(&category, &weight);
+    }
+
+    //get the weight of every category in the composite score (basis points)
+    pub fn get_category_weights(&self) -> BTreeMap<QuestCategory, u32> {
+        QuestCategory::ALL
+            .iter()
+            .map(|category| (*category, self.internal_category_weight(category)))
+            .collect()
+    }
+
+    //check if the account has completed the passed in quest
+    pub fn has_completed_quest(&self, account_id: AccountId, quest_id: u64) -> bool {
+        self.internal_has_completed_quest(&account_id, quest_id)
+    }
+
+    //take points from the score of an account that misbehaved, only the owner can call it.
+    //The category scores are reduced with it and the vouches made by the account are revoked. Returns the new score of the account
+    pub fn penalize(&mut self, account_id: AccountId, points: u64) -> u64 {
+        self.assert_owner();
+        let por = self
+            .internal_update_por(&account_id, ReputationReason::Penalized, None, |por| por.deduct(points))
+            .expect("The account has no reputation");
+        self.internal_revoke_vouches_by(&account_id);
+        por.score
+    }
+}
+
+impl Contract {
+    //get the Proof of Reputation token of the account and the reputation stored in it
+    pub(crate) fn internal_get_por(&self, account_id: &AccountId) -> Option<(TokenId, PoR)> {
+        let token_id = self.internal_token_with_title(account_id, POR_TITLE)?;
+        let metadata = self.token_metadata_by_id.get(&token_id)?;
+        let por = from_extra(metadata.extra.as_ref()?);
+        Some((token_id, por))
+    }
+
+    //change the reputation stored in the Proof of Reputation token of the account and record the change in its history.
+    //None if the account has no reputation
+    pub(crate) fn internal_update_por(
+        &mut self,
+        account_id: &AccountId,
+        reason: ReputationReason,
+        quest_id: Option<u64>,
+        update: impl FnOnce(&mut PoR),
+    ) -> Option<PoR> {
+        let token_id = self.internal_token_with_title(account_id, POR_TITLE)?;
+        let mut metadata = self.token_metadata_by_id.get(&token_id)?;
+        let mut por: PoR = from_extra(metadata.extra.as_ref()?);
+        let previous_score = por.score;
+        update(&mut por);
+        metadata.extra = Some(to_extra(&por));
+        self.token_metadata_by_id.insert(&token_id, &metadata);
+        self.internal_record_reputation_change(account_id, reason, quest_id, previous_score, por.score);
+        Some(por)
+    }
+
+    //get the weight of a category in the composite score
+    pub(crate) fn internal_category_weight(&self, category: &QuestCategory) -> u32 {
+        self.category_weights.get(category).unwrap_or(DEFAULT_CATEGORY_WEIGHT)
+    }
+
+    //get the composite score of a reputation, the score earned before the categories counts with the default weight
+    pub(crate) fn internal_composite_score(&self, por: &PoR) -> u64 {
+        let categorized: u64 = por.categories.values().sum();
+        let weighted: u128 = por
+            .categories
+            .iter()
+            .map(|(category, score)| *score as u128 * self.internal_category_weight(category) as u128)
+            .sum::<u128>()
+            + por.score.saturating_sub(categorized) as u128 * DEFAULT_CATEGORY_WEIGHT as u128;
+        (weighted / DEFAULT_CATEGORY_WEIGHT as u128) as u64
+    }
+
+    //get the quest id of a token, None if the token is not a quest token
+    pub(crate) fn internal_quest_id_of(&self, token_id: &TokenId) -> Option<u64> {
+        let metadata = self.token_metadata_by_id.get(token_id)?;
+        let title = metadata.title.unwrap_or_default();
+        if title == POR_TITLE {
+            return None;
+        }
+
+        //quest tokens store their quest id in the extra field, older tokens are matched by their title
+        let quest: Option<Quest> = metadata
+            .extra
+            .and_then(|extra| serde_json::from_str(&str::replace(&extra, "'", "\"")).ok());
+        quest
+            .and_then(|quest| quest.quest_id)
+            .or_else(|| self.internal_quest_id_by_name(&title))
+    }
+
+    //get the ids of the quests completed by the account, whoever holds their badges now
+    pub(crate) fn internal_completed_quests(&self, account_id: &AccountId) -> Vec<u64> {
+        self.completed_quests
+            .get(account_id)
+            .map(|completions| completions.into_keys().collect())
+            .unwrap_or_default()
+    }
+
+    //get the badge minted when the account completed the passed in quest and the quest information stored in it.
+    //The badge is found through the completions of the account, so it is returned even if it was transferred
+    pub(crate) fn internal_quest_badge_of(&self, account_id: &AccountId, quest_id: u64) -> Option<(TokenId, Quest)> {
+        let token_id = self.completed_quests.get(account_id)?.remove(&quest_id)?.token_id;
+        let metadata = self.token_metadata_by_id.get(&token_id)?;
+        let quest = from_extra(metadata.extra.as_ref()?);
+        Some((token_id, quest))
+    }
+
+    //record that the account completed the quest with the passed in badge
+    pub(crate) fn internal_add_quest_completion(&mut self, account_id: &AccountId, quest_id: u64, token_id: &TokenId) {
+        let mut completions = self.completed_quests.get(account_id).unwrap_or_default();
+        completions.insert(quest_id, QuestCompletion { token_id: token_id.clone(), seasons: BTreeMap::new() });
+        self.completed_quests.insert(account_id, &completions);
+    }
+
+    //remove the completion of the quest by the account and return it
+    pub(crate) fn internal_remove_quest_completion(&mut self, account_id: &AccountId, quest_id: u64) -> Option<QuestCompletion> {
+        let mut completions = self.completed_quests.get(account_id)?;
+        let completion = completions.remove(&quest_id)?;
+        if completions.is_empty() {
+            self.completed_quests.remove(account_id);
+        } else {
+            self.completed_quests.insert(account_id, &completions);
+        }
+        Some(completion)
+    }
+
+    //check if the account has completed the passed in quest
+    pub(crate) fn internal_has_completed_quest(&self, account_id: &AccountId, quest_id: u64) -> bool {
+        self.completed_quests
+            .get(account_id)
+            .is_some_and(|completions| completions.contains_key(&quest_id))
+    }
+}
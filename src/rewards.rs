@@ -0,0 +1,224 @@
+use crate::*;
+use near_sdk::{ext_contract, Gas, PromiseResult};
+
+//GAS attached to the ft_transfer of a reward and to its callback
+const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+const GAS_FOR_RESOLVE_REWARD: Gas = Gas(10_000_000_000_000);
+//ft_transfer requires exactly 1 yoctoNEAR
+const ONE_YOCTO: Balance = 1;
+
+//NEP-141 tokens paid to the accounts that complete a quest
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct QuestReward {
+    //contract of the token, the reward is paid from the pool of the quest
+    pub token_id: AccountId,
+    //amount paid on each completion
+    pub amount: U128,
+    //total amount the quest can pay, once it is reached the quest only gives points
+    pub budget: U128,
+}
+
+//a reward whose transfer failed (for instance because the account is not registered in the token),
+//it can be claimed with claim_rewards. Rewards held under the human gating policy are kept the same way
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnclaimedReward {
+    //None for rewards whose manual claim failed again
+    pub quest_id: Option<u64>,
+    pub token_id: AccountId,
+    pub amount: U128,
+}
+
+#[ext_contract(ext_ft)]
+pub trait FungibleToken {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[ext_contract(ext_rewards)]
+trait RewardResolver {
+    fn resolve_reward(&mut self, account_id: AccountId, quest_id: Option<u64>, token_id: AccountId, amount: U128);
+    fn resolve_reward_pool_withdraw(&mut self, quest_id: u64, amount: U128);
+}
+
+#[near_bindgen]
+impl Contract {
+    // NEP-141 receiver to fund the reward pool of a quest: partners send the tokens with ft_transfer_call and the
+    // quest id as msg. Tokens sent to a quest that doesn't pay them as reward are given back.
+    pub fn ft_on_transfer(&mut self, sender_id: AccountId, amount: U128, msg: String) -> PromiseOrValue<U128> {
+        let token_id = env::predecessor_account_id();
+        let quest_id = match msg.parse::<u64>() {
+            Ok(quest_id) => quest_id,
+            Err(_) => return PromiseOrValue::Value(amount),
+        };
+        let rewarded = self
+            .quests
+            .get(&quest_id)
+            .is_some_and(|quest| quest.reward.is_some_and(|reward| reward.token_id == token_id));
+        if !rewarded {
+            return PromiseOrValue::Value(amount);
+        }
+
+        let balance = self.reward_pools.get(&quest_id).unwrap_or(0);
+        self.reward_pools.insert(&quest_id, &(balance + amount.0));
+        env::log_str(&format!("{} funded the reward pool of quest {} with {} of {}", sender_id, quest_id, amount.0, token_id));
+        PromiseOrValue::Value(U128(0))
+    }
+
+    //get the balance of the reward pool of a quest
+    pub fn get_reward_pool(&self, quest_id: u64) -> U128 {
+        U128(self.reward_pools.get(&quest_id).unwrap_or(0))
+    }
+
+    // Method to take tokens out of the reward pool of a quest (to end a campaign or move the funds to another quest),
+    // only the governance account (or the owner if there is none) can call it. If the transfer fails the amount goes
+    // back to the pool
+    #[payable]
+    pub fn withdraw_reward_pool(&mut self, quest_id: u64, amount: U128, receiver_id: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_governance();
+        let token_id = self
+            .quests
+            .get(&quest_id)
+            .and_then(|quest| quest.reward)
+            .map(|reward| reward.token_id)
+            .expect("The quest has no reward");
+        let pool = self.reward_pools.get(&quest_id).unwrap_or(0);
+        assert!(amount.0 > 0 && amount.0 <= pool, "The amount must be between 1 and the balance of the pool ({})", pool);
+
+        self.reward_pools.insert(&quest_id, &(pool - amount.0));
+        let memo = format!("Withdrawal of the reward pool of quest {}", quest_id);
+        ext_ft::ft_transfer(receiver_id, amount, Some(memo), token_id, ONE_YOCTO, GAS_FOR_FT_TRANSFER).then(
+            ext_rewards::resolve_reward_pool_withdraw(quest_id, amount, env::current_account_id(), NO_DEPOSIT, GAS_FOR_RESOLVE_REWARD),
+        )
+    }
+
+    //give the amount back to the pool if the transfer of the withdrawal failed
+    #[private]
+    pub fn resolve_reward_pool_withdraw(&mut self, quest_id: u64, amount: U128) {
+        assert_self();
+        assert_eq!(env::promise_results_count(), 1, "Éste es un método callback");
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        let pool = self.reward_pools.get(&quest_id).unwrap_or(0);
+        self.reward_pools.insert(&quest_id, &(pool + amount.0));
+    }
+
+    //get the amount of its budget a quest has already paid
+    pub fn get_quest_rewards_paid(&self, quest_id: u64) -> U128 {
+        U128(self.quest_rewards_paid.get(&quest_id).unwrap_or(0))
+    }
+
+    pub fn get_unclaimed_rewards(&self, account_id: AccountId) -> Vec<UnclaimedReward> {
+        self.unclaimed_rewards.get(&account_id).unwrap_or_default()
+    }
+
+    //get the rewards held until the account proves it is human
+    pub fn get_pending_rewards(&self, account_id: AccountId) -> Vec<UnclaimedReward> {
+        self.pending_rewards.get(&account_id).unwrap_or_default()
+    }
+
+    //transfer again the rewards of a token whose transfer failed, if it fails again they are kept as unclaimed
+    pub fn claim_rewards(&mut self, token_id: AccountId) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let (claimed, unclaimed): (Vec<UnclaimedReward>, Vec<UnclaimedReward>) = self
+            .unclaimed_rewards
+            .get(&account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|reward| reward.token_id == token_id);
+        let amount: Balance = claimed.iter().map(|reward| reward.amount.0).sum();
+        assert!(amount > 0, "There are no rewards to claim");
+
+        if unclaimed.is_empty() {
+            self.unclaimed_rewards.remove(&account_id);
+        } else {
+            self.unclaimed_rewards.insert(&account_id, &unclaimed);
+        }
+        self.internal_transfer_reward(account_id, None, token_id, amount)
+    }
+
+    //keep track of the reward if its transfer failed
+    #[private]
+    pub fn resolve_reward(&mut self, account_id: AccountId, quest_id: Option<u64>, token_id: AccountId, amount: U128) {
+        assert_self();
+        assert_eq!(env::promise_results_count(), 1, "Éste es un método callback");
+        if let PromiseResult::Successful(_) = env::promise_result(0) {
+            return;
+        }
+
+        let mut unclaimed = self.unclaimed_rewards.get(&account_id).unwrap_or_default();
+        unclaimed.push(UnclaimedReward { quest_id, token_id, amount });
+        self.unclaimed_rewards.insert(&account_id, &unclaimed);
+    }
+}
+
+impl Contract {
+    //pay the reward of a completed quest if the quest has budget left and its pool has enough balance. When the score
+    //of the quest is held under the human gating policy the reward is taken from the pool and held with it
+    pub(crate) fn internal_pay_reward(&mut self, account_id: &AccountId, quest_id: u64, reward: &QuestReward, held: bool) {
+        let paid = self.quest_rewards_paid.get(&quest_id).unwrap_or(0);
+        let pool = self.reward_pools.get(&quest_id).unwrap_or(0);
+        if paid + reward.amount.0 > reward.budget.0 || pool < reward.amount.0 {
+            return;
+        }
+
+        self.quest_rewards_paid.insert(&quest_id, &(paid + reward.amount.0));
+        self.reward_pools.insert(&quest_id, &(pool - reward.amount.0));
+        if held {
+            let mut pending = self.pending_rewards.get(account_id).unwrap_or_default();
+            pending.push(UnclaimedReward { quest_id: Some(quest_id), token_id: reward.token_id.clone(), amount: reward.amount });
+            self.pending_rewards.insert(account_id, &pending);
+        } else {
+            self.internal_transfer_reward(account_id.clone(), Some(quest_id), reward.token_id.clone(), reward.amount.0);
+        }
+    }
+
+    //transfer the rewards held until the account proved it is human
+    pub(crate) fn internal_release_pending_rewards(&mut self, account_id: &AccountId) {
+        for reward in self.pending_rewards.remove(account_id).unwrap_or_default() {
+            self.internal_transfer_reward(account_id.clone(), reward.quest_id, reward.token_id, reward.amount.0);
+        }
+    }
+
+    //give the rewards held for a revoked quest back to its pool and budget
+    pub(crate) fn internal_take_pending_rewards(&mut self, account_id: &AccountId, quest_id: u64) {
+        let (taken, pending): (Vec<UnclaimedReward>, Vec<UnclaimedReward>) = self
+            .pending_rewards
+            .get(account_id)
+            .unwrap_or_default()
+            .into_iter()
+            .partition(|reward| reward.quest_id == Some(quest_id));
+        if taken.is_empty() {
+            return;
+        }
+
+        let amount: Balance = taken.iter().map(|reward| reward.amount.0).sum();
+        let paid = self.quest_rewards_paid.get(&quest_id).unwrap_or(0);
+        self.quest_rewards_paid.insert(&quest_id, &paid.saturating_sub(amount));
+        let pool = self.reward_pools.get(&quest_id).unwrap_or(0);
+        self.reward_pools.insert(&quest_id, &(pool + amount));
+        if pending.is_empty() {
+            self.pending_rewards.remove(account_id);
+        } else {
+            self.pending_rewards.insert(account_id, &pending);
+        }
+    }
+
+    //transfer a reward and check the result in resolve_reward
+    fn internal_transfer_reward(&mut self, account_id: AccountId, quest_id: Option<u64>, token_id: AccountId, amount: Balance) -> Promise {
+        let memo = quest_id.map(|quest_id| format!("Reward of quest {}", quest_id));
+        ext_ft::ft_transfer(account_id.clone(), U128(amount), memo, token_id.clone(), ONE_YOCTO, GAS_FOR_FT_TRANSFER)
+            .then(ext_rewards::resolve_reward(
+                account_id,
+                quest_id,
+                token_id,
+                U128(amount),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_RESOLVE_REWARD,
+            ))
+    }
+}
@@ -0,0 +1,295 @@
+use crate::*;
+use near_sdk::collections::TreeMap;
+
+//number of steps done by a call to close_season when no limit is passed in
+const DEFAULT_ARCHIVE_STEPS: u64 = 100;
+
+//a reputation campaign with its own quests and ranking
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Season {
+    pub name: String,
+    //block timestamps (in nanoseconds) of the window in which the quests of the season can be claimed
+    pub starts_at: U64,
+    pub ends_at: U64,
+    pub quest_ids: Vec<u64>,
+    //closed seasons have a frozen final ranking
+    #[serde(default)]
+    pub closed: bool,
+}
+
+impl Season {
+    //check if the quests of the season can be claimed right now
+    pub(crate) fn is_open(&self) -> bool {
+        let now = env::block_timestamp();
+        !self.closed && self.starts_at.0 <= now && now <= self.ends_at.0
+    }
+}
+
+//the season as returned from the view calls
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonSeason {
+    pub season_id: u64,
+    #[serde(flatten)]
+    pub season: Season,
+}
+
+//the position of an account in the ranking of a season
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SeasonRankingEntry {
+    pub account_id: AccountId,
+    pub score: u64,
+}
+
+//where the archive of the final ranking of a season is, each step handles one account
+#[derive(BorshDeserialize, BorshSerialize, PartialEq)]
+pub enum ArchiveStage {
+    //reading the season scores into the sorted accounts
+    Sorting { index: u64 },
+    //writing the sorted accounts into the ranking in order
+    Ranking { last: Option<(u64, AccountId)> },
+    Done,
+}
+
+//the frozen final ranking of a closed season, built over several calls of close_season
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SeasonArchive {
+    //score of each account sorted by (u64::MAX - score, account_id), so the highest scores come first
+    pub sorted: TreeMap<(u64, AccountId), u64>,
+    pub ranking: Vector<SeasonRankingEntry>,
+    pub stage: ArchiveStage,
+}
+
+impl SeasonArchive {
+    //do one step of the archive with the scores of the season
+    fn step(&mut self, scores: Option<&UnorderedMap<AccountId, u64>>) {
+        self.stage = match self.stage {
+            ArchiveStage::Sorting { index } => {
+                match scores.and_then(|scores| scores.keys_as_vector().get(index)) {
+                    None => ArchiveStage::Ranking { last: None },
+                    Some(account_id) => {
+                        let score = scores.and_then(|scores| scores.get(&account_id)).unwrap_or(0);
+                        self.sorted.insert(&(u64::MAX - score, account_id), &score);
+                        ArchiveStage::Sorting { index: index + 1 }
+                    }
+                }
+            }
+            ArchiveStage::Ranking { ref last } => {
+                let next = match last {
+                    None => self.sorted.min(),
+                    Some(last) => self.sorted.higher(last),
+                };
+                match next {
+                    Some(key) => {
+                        let score = self.sorted.get(&key).unwrap();
+                        self.ranking.push(&SeasonRankingEntry { account_id: key.1.clone(), score });
+                        ArchiveStage::Ranking { last: Some(key) }
+                    }
+                    None => ArchiveStage::Done,
+                }
+            }
+            ArchiveStage::Done => ArchiveStage::Done,
+        };
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    //create a new season, only the governance account (or the owner if there is none) can call it
+    pub fn add_season(&mut self, season_id: u64, season: Season) {
+        self.assert_governance();
+        assert!(season.starts_at.0 < season.ends_at.0, "The season must start before it ends");
+        assert!(!season.closed, "A new season can't be closed");
+        for quest_id in season.quest_ids.iter() {
+            assert!(self.quests.get(quest_id).is_some(), "Quest {} not found", quest_id);
+        }
+        assert!(
+            self.seasons.insert(&season_id, &season).is_none(),
+            "Season already exists"
+        );
+
+        //index the season by its quests so the claims only read the seasons of their quest
+        for quest_id in season.quest_ids.iter() {
+            let mut seasons = self.seasons_by_quest.get(quest_id).unwrap_or_default();
+            if !seasons.contains(&season_id) {
+                seasons.push(season_id);
+                self.seasons_by_quest.insert(quest_id, &seasons);
+            }
+        }
+    }
+
+    // Close a season that has ended and archive its final ranking, only the governance account (or the owner if there is none) can call it. The archive is split
+    // in steps so it fits in the gas of a transaction: each call does up to limit steps and the next call resumes it.
+    // The season is closed on the first call. Returns true once the final ranking is archived
+    pub fn close_season(&mut self, season_id: u64, limit: Option<u64>) -> bool {
+        self.assert_governance();
+        let mut season = self.seasons.get(&season_id).expect("Season not found");
+        if !season.closed {
+            assert!(env::block_timestamp() > season.ends_at.0, "The season has not ended yet");
+            season.closed = true;
+            self.seasons.insert(&season_id, &season);
+            let archive = SeasonArchive {
+                sorted: TreeMap::new(StorageKey::SeasonArchiveSorted { season_id }.try_to_vec().unwrap()),
+                ranking: Vector::new(StorageKey::SeasonArchiveRanking { season_id }.try_to_vec().unwrap()),
+                stage: ArchiveStage::Sorting { index: 0 },
+            };
+            self.season_archives.insert(&season_id, &archive);
+        }
+
+        let mut archive = self.season_archives.get(&season_id).unwrap();
+        assert!(archive.stage != ArchiveStage::Done, "The season is already closed");
+        let scores = self.season_scores.get(&season_id);
+        for _ in 0..limit.unwrap_or(DEFAULT_ARCHIVE_STEPS) {
+            if archive.stage == ArchiveStage::Done {
+                break;
+            }
+            archive.step(scores.as_ref());
+        }
+        self.season_archives.insert(&season_id, &archive);
+        archive.stage == ArchiveStage::Done
+    }
+
+    //get a season
+    pub fn get_season(&self, season_id: u64) -> Option<JsonSeason> {
+        self.seasons
+            .get(&season_id)
+            .map(|season| JsonSeason { season_id, season })
+    }
+
+    //get the seasons using pagination
+    pub fn get_seasons(&self, from_index: Option<U128>, limit: Option<u64>) -> Vec<JsonSeason> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.seasons.iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(season_id, season)| JsonSeason { season_id, season })
+            .collect()
+    }
+
+    //get the score of the account in a season
+    pub fn get_season_score(&self, season_id: u64, account_id: AccountId) -> u64 {
+        self.season_scores
+            .get(&season_id)
+            .and_then(|scores| scores.get(&account_id))
+            .unwrap_or(0)
+    }
+
+    //get the ranking of a season using pagination, the frozen final ranking once the season is archived
+    pub fn get_season_ranking(&self, season_id: u64, from_index: Option<U128>, limit: Option<u64>) -> Vec<SeasonRankingEntry> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+        if let Some(archive) = self.season_archives.get(&season_id).filter(|archive| archive.stage == ArchiveStage::Done) {
+            //take the first "limit" elements. If we didn't specify a limit, use 50
+            let end = std::cmp::min(archive.ranking.len(), (start as u64).saturating_add(limit.unwrap_or(50)));
+            return (start as u64..end).filter_map(|index| archive.ranking.get(index)).collect();
+        }
+
+        self.internal_season_ranking(season_id).into_iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    //get the ids of the open seasons that include the quest
+    pub(crate) fn internal_open_seasons_of(&self, quest_id: u64) -> Vec<u64> {
+        self.seasons_by_quest
+            .get(&quest_id)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|season_id| self.seasons.get(season_id).is_some_and(|season| season.is_open()))
+            .collect()
+    }
+
+    //check if the quest can be claimed right now, quests that don't belong to any season can always be claimed
+    pub(crate) fn internal_is_in_season(&self, quest_id: u64) -> bool {
+        let seasons = self.seasons_by_quest.get(&quest_id).unwrap_or_default();
+        seasons.is_empty() || !self.internal_open_seasons_of(quest_id).is_empty()
+    }
+
+    //get the score of a quest for each open season that includes it
+    pub(crate) fn internal_open_season_scores(&self, quest_id: u64, score: u64) -> BTreeMap<u64, u64> {
+        self.internal_open_seasons_of(quest_id)
+            .into_iter()
+            .map(|season_id| (season_id, score))
+            .collect()
+    }
+
+    //add the score of a quest to the open seasons that include it
+    pub(crate) fn internal_add_season_score(&mut self, account_id: &AccountId, quest_id: u64, score: u64) {
+        let season_scores = self.internal_open_season_scores(quest_id, score);
+        self.internal_add_to_seasons(account_id, quest_id, &season_scores);
+    }
+
+    //add the passed in score of each season to the account, skipping the closed seasons whose final ranking is frozen.
+    //The score added to each season is recorded in the completion of the quest so it can be taken back if the quest is revoked
+    pub(crate) fn internal_add_to_seasons(&mut self, account_id: &AccountId, quest_id: u64, season_scores: &BTreeMap<u64, u64>) {
+        let mut added = BTreeMap::new();
+        for (season_id, score) in season_scores.iter() {
+            let season_id = *season_id;
+            if self.seasons.get(&season_id).is_none_or(|season| season.closed) {
+                continue;
+            }
+            let mut scores = self.season_scores.get(&season_id).unwrap_or_else(|| {
+                UnorderedMap::new(
+                    StorageKey::SeasonScoresInner { season_id }
+                        .try_to_vec()
+                        .unwrap(),
+                )
+            });
+            let season_score = scores.get(account_id).unwrap_or(0) + score;
+            scores.insert(account_id, &season_score);
+            self.season_scores.insert(&season_id, &scores);
+            if *score > 0 {
+                added.insert(season_id, *score);
+            }
+        }
+
+        if added.is_empty() {
+            return;
+        }
+        let mut completions = self.completed_quests.get(account_id).unwrap_or_default();
+        if let Some(completion) = completions.get_mut(&quest_id) {
+            for (season_id, score) in added {
+                *completion.seasons.entry(season_id).or_insert(0) += score;
+            }
+            self.completed_quests.insert(account_id, &completions);
+        }
+    }
+
+    //take back the score that a revoked completion added to the seasons. The final ranking of the closed seasons is frozen
+    pub(crate) fn internal_take_season_scores(&mut self, account_id: &AccountId, completion: &QuestCompletion) {
+        for (season_id, score) in completion.seasons.iter() {
+            if self.seasons.get(season_id).is_none_or(|season| season.closed) {
+                continue;
+            }
+            if let Some(mut scores) = self.season_scores.get(season_id) {
+                let season_score = scores.get(account_id).unwrap_or(0).saturating_sub(*score);
+                scores.insert(account_id, &season_score);
+                self.season_scores.insert(season_id, &scores);
+            }
+        }
+    }
+
+    //get the accounts of a season sorted by their score
+    pub(crate) fn internal_season_ranking(&self, season_id: u64) -> Vec<SeasonRankingEntry> {
+        let mut ranking: Vec<SeasonRankingEntry> = match self.season_scores.get(&season_id) {
+            Some(scores) => scores
+                .iter()
+                .map(|(account_id, score)| SeasonRankingEntry { account_id, score })
+                .collect(),
+            None => vec![],
+        };
+        ranking.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.account_id.cmp(&b.account_id)));
+        ranking
+    }
+}
@@ -0,0 +1,217 @@
+use crate::*;
+use near_sdk::collections::TreeMap;
+
+//number of steps done by a call to build_snapshot when no limit is passed in
+const DEFAULT_SNAPSHOT_STEPS: u64 = 100;
+
+//where a snapshot build is, each step handles one token, one leaf or one node of the tree
+#[derive(BorshDeserialize, BorshSerialize, PartialEq)]
+pub enum SnapshotStage {
+    //reading the Proof of Reputation tokens into the sorted leaves, in the order of the sorted token IDs so tokens
+    //burned during the build don't make it skip tokens. last is the sort key of the last token read
+    Collecting { last: Option<(u64, TokenId)> },
+    //hashing the leaves in the order of the accounts
+    Hashing { last: Option<AccountId> },
+    //hashing the nodes of a level of the tree into the next one
+    Reducing { level_start: u64, level_len: u64, index: u64 },
+    Done,
+}
+
+//merkle tree of the (account, score) pairs of the Proof of Reputation holders at a block timestamp
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Snapshot {
+    pub timestamp: u64,
+    //score of each account, sorted by account
+    pub leaves: TreeMap<AccountId, u64>,
+    //nodes of the tree level by level, starting with the leaves
+    pub nodes: Vector<CryptoHash>,
+    pub root: Option<CryptoHash>,
+    pub stage: SnapshotStage,
+}
+
+//the snapshot as returned from the view calls
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct JsonSnapshot {
+    pub snapshot_id: u64,
+    pub timestamp: U64,
+    //number of accounts in the snapshot (so far, while it is being built)
+    pub accounts: u64,
+    //None until the build is complete
+    pub root: Option<Base64VecU8>,
+}
+
+//an account of a snapshot and its score
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SnapshotLeaf {
+    pub account_id: AccountId,
+    pub score: u64,
+}
+
+fn sha256(value: &[u8]) -> CryptoHash {
+    env::sha256(value).try_into().unwrap()
+}
+
+//hash of the leaf of an account: sha256 of the borsh serialization of (account_id, score)
+pub(crate) fn snapshot_leaf_hash(account_id: &AccountId, score: u64) -> CryptoHash {
+    sha256(&(account_id, score).try_to_vec().unwrap())
+}
+
+//hash of a parent node: sha256 of its two children sorted, so the proofs don't need the side of each hash
+pub(crate) fn snapshot_node_hash(a: &CryptoHash, b: &CryptoHash) -> CryptoHash {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    sha256(&[left.as_slice(), right.as_slice()].concat())
+}
+
+#[near_bindgen]
+impl Contract {
+    // Build a snapshot of the reputation, only the owner can call it. The build is split in steps so it fits in the gas
+    // of a transaction: each call does up to limit steps and the next call resumes it, the snapshot is complete when it
+    // has a root. The scores are taken from the checkpoints at the block timestamp in which the build started.
+    pub fn build_snapshot(&mut self, limit: Option<u64>) -> JsonSnapshot {
+        self.assert_owner();
+        let snapshot_id = match self.snapshot_in_progress {
+            Some(snapshot_id) => snapshot_id,
+            None => {
+                let snapshot_id = self.snapshots.len();
+                let snapshot = Snapshot {
+                    timestamp: env::block_timestamp(),
+                    leaves: TreeMap::new(StorageKey::SnapshotLeaves { snapshot_id }.try_to_vec().unwrap()),
+                    nodes: Vector::new(StorageKey::SnapshotNodes { snapshot_id }.try_to_vec().unwrap()),
+                    root: None,
+                    stage: SnapshotStage::Collecting { last: None },
+                };
+                self.snapshots.insert(&snapshot_id, &snapshot);
+                snapshot_id
+            }
+        };
+
+        let mut snapshot = self.snapshots.get(&snapshot_id).unwrap();
+        for _ in 0..limit.unwrap_or(DEFAULT_SNAPSHOT_STEPS) {
+            if snapshot.stage == SnapshotStage::Done {
+                break;
+            }
+            self.internal_snapshot_step(&mut snapshot);
+        }
+        self.snapshot_in_progress = match snapshot.stage {
+            SnapshotStage::Done => None,
+            _ => Some(snapshot_id),
+        };
+        self.snapshots.insert(&snapshot_id, &snapshot);
+        JsonSnapshot::from(snapshot_id, &snapshot)
+    }
+
+    pub fn get_snapshot(&self, snapshot_id: u64) -> Option<JsonSnapshot> {
+        self.snapshots
+            .get(&snapshot_id)
+            .map(|snapshot| JsonSnapshot::from(snapshot_id, &snapshot))
+    }
+
+    //get the accounts of a snapshot and their score, sorted by account, to build the proofs off-chain
+    pub fn get_snapshot_leaves(&self, snapshot_id: u64, from_index: Option<U128>, limit: Option<u64>) -> Vec<SnapshotLeaf> {
+        let snapshot = self.snapshots.get(&snapshot_id).expect("Snapshot not found");
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        snapshot.leaves.iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .map(|(account_id, score)| SnapshotLeaf { account_id, score })
+            .collect()
+    }
+
+    //check that the account had the passed in score in a complete snapshot, with the sibling hashes from the leaf to the root
+    pub fn verify_snapshot_proof(&self, snapshot_id: u64, account_id: AccountId, score: u64, proof: Vec<Base64VecU8>) -> bool {
+        let root = match self.snapshots.get(&snapshot_id).and_then(|snapshot| snapshot.root) {
+            Some(root) => root,
+            None => return false,
+        };
+        let mut hash = snapshot_leaf_hash(&account_id, score);
+        for sibling in proof {
+            let sibling: CryptoHash = match sibling.0.try_into() {
+                Ok(sibling) => sibling,
+                Err(_) => return false,
+            };
+            hash = snapshot_node_hash(&hash, &sibling);
+        }
+        hash == root
+    }
+}
+
+impl JsonSnapshot {
+    fn from(snapshot_id: u64, snapshot: &Snapshot) -> Self {
+        JsonSnapshot {
+            snapshot_id,
+            timestamp: U64(snapshot.timestamp),
+            accounts: snapshot.leaves.len(),
+            root: snapshot.root.map(|root| Base64VecU8(root.to_vec())),
+        }
+    }
+}
+
+impl Contract {
+    //do one step of the build of a snapshot
+    fn internal_snapshot_step(&self, snapshot: &mut Snapshot) {
+        snapshot.stage = match snapshot.stage {
+            SnapshotStage::Collecting { ref last } => {
+                let next = match last {
+                    None => self.sorted_token_ids.min(),
+                    Some(last) => self.sorted_token_ids.higher(last),
+                };
+                match next {
+                    None => SnapshotStage::Hashing { last: None },
+                    Some(key) => {
+                        let token_id = &key.1;
+                        let is_por = self
+                            .token_metadata_by_id
+                            .get(token_id)
+                            .is_some_and(|metadata| metadata.title.as_deref() == Some(POR_TITLE));
+                        if let Some(token) = self.tokens_by_id.get(token_id).filter(|_| is_por) {
+                            let score = self.get_score_at(token.owner_id.clone(), U64(snapshot.timestamp));
+                            if score > 0 && !snapshot.leaves.contains_key(&token.owner_id) {
+                                snapshot.leaves.insert(&token.owner_id, &score);
+                            }
+                        }
+                        SnapshotStage::Collecting { last: Some(key) }
+                    }
+                }
+            }
+            SnapshotStage::Hashing { ref last } => {
+                let next = match last {
+                    None => snapshot.leaves.min(),
+                    Some(last) => snapshot.leaves.higher(last),
+                };
+                match next {
+                    Some(account_id) => {
+                        let score = snapshot.leaves.get(&account_id).unwrap();
+                        snapshot.nodes.push(&snapshot_leaf_hash(&account_id, score));
+                        SnapshotStage::Hashing { last: Some(account_id) }
+                    }
+                    None => SnapshotStage::Reducing { level_start: 0, level_len: snapshot.nodes.len(), index: 0 },
+                }
+            }
+            SnapshotStage::Reducing { level_start, level_len, index } => {
+                if level_len <= 1 {
+                    //a snapshot without accounts has an all-zero root
+                    snapshot.root = Some(snapshot.nodes.get(level_start).unwrap_or([0; 32]));
+                    SnapshotStage::Done
+                } else if index >= level_len {
+                    SnapshotStage::Reducing { level_start: level_start + level_len, level_len: level_len.div_ceil(2), index: 0 }
+                } else {
+                    //the last node of a level without a sibling goes up unchanged
+                    let left = snapshot.nodes.get(level_start + index).unwrap();
+                    let parent = match snapshot.nodes.get(level_start + index + 1).filter(|_| index + 1 < level_len) {
+                        Some(right) => snapshot_node_hash(&left, &right),
+                        None => left,
+                    };
+                    snapshot.nodes.push(&parent);
+                    SnapshotStage::Reducing { level_start, level_len, index: index + 2 }
+                }
+            }
+            SnapshotStage::Done => SnapshotStage::Done,
+        };
+    }
+}
@@ -0,0 +1,236 @@
+use crate::*;
+
+//the amount of storage (in bytes) reserved for a single quest claim (quest token + Proof of Reputation token
+//+ their entries in the sorted token indexes + the first entries of the reputation history and the score checkpoints)
+pub const STORAGE_BYTES_PER_CLAIM: u64 = 3_500;
+
+//storage balance of an account as it's kept on the contract
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct StorageAccount {
+    //total amount of yoctoNEAR deposited by the account
+    pub deposit: Balance,
+    //number of bytes already charged against the deposit
+    pub bytes_used: u64,
+    //number of bytes reserved for the claims waiting for their verification call
+    pub bytes_reserved: u64,
+}
+
+//storage balance returned from the view calls as per the NEP-145 standard
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalance {
+    pub total: U128,
+    pub available: U128,
+}
+
+//minimum and maximum storage balance an account can have
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageBalanceBounds {
+    pub min: U128,
+    pub max: Option<U128>,
+}
+
+pub trait StorageManagement {
+    //deposit NEAR to pay for the storage of the quests claimed by the account
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance;
+
+    //withdraw the part of the deposit that is not being used for storage
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance;
+
+    //get the storage balance of the passed in account
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+
+    //get the minimum and maximum storage balance allowed
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds;
+}
+
+//cost in yoctoNEAR of storing the passed in number of bytes
+pub(crate) fn storage_cost(bytes: u64) -> Balance {
+    env::storage_byte_cost() * Balance::from(bytes)
+}
+
+impl StorageAccount {
+    //amount of yoctoNEAR that is not being used to pay for storage or reserved for a claim
+    pub(crate) fn available(&self) -> Balance {
+        self.deposit.saturating_sub(storage_cost(self.bytes_used + self.bytes_reserved))
+    }
+
+    //converts the storage account into the balance returned by the NEP-145 view calls
+    pub(crate) fn to_storage_balance(&self) -> StorageBalance {
+        StorageBalance {
+            total: U128(self.deposit),
+            available: U128(self.available()),
+        }
+    }
+}
+
+impl Contract {
+    //add the passed in amount to the storage deposit of the account
+    pub(crate) fn internal_storage_deposit(&mut self, account_id: &AccountId, amount: Balance) -> StorageAccount {
+        let mut storage_account = self.storage_accounts.get(account_id).unwrap_or(StorageAccount {
+            deposit: 0,
+            bytes_used: 0,
+            bytes_reserved: 0,
+        });
+        storage_account.deposit += amount;
+        self.storage_accounts.insert(account_id, &storage_account);
+        storage_account
+    }
+
+    //get the amount of yoctoNEAR the account has available to pay for storage
+    pub(crate) fn internal_storage_available(&self, account_id: &AccountId) -> Balance {
+        self.storage_accounts
+            .get(account_id)
+            .map(|storage_account| storage_account.available())
+            .unwrap_or(0)
+    }
+
+    //charge the bytes used by a mint against the storage deposit of the account
+    pub(crate) fn internal_charge_storage(&mut self, account_id: &AccountId, bytes_used: u64) {
+        let mut storage_account = self
+            .storage_accounts
+            .get(account_id)
+            .expect("The account is not registered for storage");
+
+        //make sure that the deposit covers the storage already used and reserved plus the new bytes
+        storage_account.bytes_used += bytes_used;
+        let required_cost = storage_cost(storage_account.bytes_used + storage_account.bytes_reserved);
+        assert!(
+            required_cost <= storage_account.deposit,
+            "Must deposit {} yoctoNEAR to cover storage",
+            required_cost - storage_account.deposit,
+        );
+
+        self.storage_accounts.insert(account_id, &storage_account);
+    }
+
+    //reserve bytes of the storage balance of the account for a claim, the caller makes sure they are available
+    pub(crate) fn internal_reserve_storage(&mut self, account_id: &AccountId, bytes: u64) {
+        let mut storage_account = self
+            .storage_accounts
+            .get(account_id)
+            .expect("The account is not registered for storage");
+        storage_account.bytes_reserved += bytes;
+        self.storage_accounts.insert(account_id, &storage_account);
+    }
+
+    //release the bytes reserved for a claim and charge the bytes the claim really used. It doesn't panic, so it can
+    //run in the callbacks: the reservation is sized for a claim and if the deposit doesn't cover the bytes used
+    //the account has no available balance until it deposits more
+    pub(crate) fn internal_settle_storage(&mut self, account_id: &AccountId, reserved_bytes: u64, bytes_used: u64) {
+        if reserved_bytes == 0 && bytes_used == 0 {
+            return;
+        }
+        if let Some(mut storage_account) = self.storage_accounts.get(account_id) {
+            storage_account.bytes_reserved = storage_account.bytes_reserved.saturating_sub(reserved_bytes);
+            storage_account.bytes_used += bytes_used;
+            self.storage_accounts.insert(account_id, &storage_account);
+        }
+    }
+
+    //take back part of the storage deposit of the account and transfer it to the account that made it
+    pub(crate) fn internal_refund_storage_deposit(&mut self, account_id: &AccountId, amount: Balance, refund_to: &AccountId) {
+        if let Some(mut storage_account) = self.storage_accounts.get(account_id) {
+            //we can't give back more than what is still available in the account
+            let refund = std::cmp::min(amount, storage_account.available());
+            if refund > 0 {
+                storage_account.deposit -= refund;
+                self.storage_accounts.insert(account_id, &storage_account);
+                Promise::new(refund_to.clone()).transfer(refund);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl StorageManagement for Contract {
+    #[payable]
+    fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+    ) -> StorageBalance {
+        let amount = env::attached_deposit();
+        //if no account ID was passed in, the deposit is made for the caller
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        let min_balance = self.storage_balance_bounds().min.0;
+        let is_registered = self.storage_accounts.contains_key(&account_id);
+
+        if registration_only.unwrap_or(false) {
+            //if the account is already registered we refund the full deposit
+            if is_registered {
+                if amount > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(amount);
+                }
+                return self.storage_balance_of(account_id).unwrap();
+            }
+
+            //otherwise we only keep the minimum balance and refund the rest
+            assert!(
+                amount >= min_balance,
+                "The attached deposit is less than the minimum storage balance of {} yoctoNEAR",
+                min_balance,
+            );
+            let refund = amount - min_balance;
+            if refund > 0 {
+                Promise::new(env::predecessor_account_id()).transfer(refund);
+            }
+            return self.internal_storage_deposit(&account_id, min_balance).to_storage_balance();
+        }
+
+        //a new account must deposit at least the minimum balance
+        assert!(
+            is_registered || amount >= min_balance,
+            "The attached deposit is less than the minimum storage balance of {} yoctoNEAR",
+            min_balance,
+        );
+        self.internal_storage_deposit(&account_id, amount).to_storage_balance()
+    }
+
+    #[payable]
+    fn storage_withdraw(&mut self, amount: Option<U128>) -> StorageBalance {
+        //assert that the user attached exactly 1 yoctoNEAR for security reasons
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut storage_account = self
+            .storage_accounts
+            .get(&account_id)
+            .expect("The account is not registered for storage");
+
+        //if no amount was passed in, we withdraw everything that is available
+        let available = storage_account.available();
+        let amount = amount.map(|amount| amount.0).unwrap_or(available);
+        assert!(
+            amount <= available,
+            "The amount is greater than the available storage balance of {} yoctoNEAR",
+            available,
+        );
+
+        storage_account.deposit -= amount;
+        self.storage_accounts.insert(&account_id, &storage_account);
+        if amount > 0 {
+            Promise::new(account_id).transfer(amount);
+        }
+
+        storage_account.to_storage_balance()
+    }
+
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance> {
+        self.storage_accounts
+            .get(&account_id)
+            .map(|storage_account| storage_account.to_storage_balance())
+    }
+
+    fn storage_balance_bounds(&self) -> StorageBalanceBounds {
+        //an account needs enough balance to pay for at least one quest claim
+        StorageBalanceBounds {
+            min: U128(storage_cost(STORAGE_BYTES_PER_CLAIM)),
+            max: None,
+        }
+    }
+}
@@ -1,17 +1,25 @@
 /* unit tests */
 #[cfg(test)]
 use crate::Contract;
-use crate::TokenMetadata;
+use crate::{ClaimError, ClaimOutcome, EarlyBird, EligibilityReport, TokenFilter, TokenIdInfo, TokenIdScheme, TokenKind, QuestCategory, QuestConfig, QuestStatus, QuestSupply, QuestVerifier, RepeatConfig, StreakMultiplier, ReputationTier, Season, SeasonRankingEntry, SignedClaim, VouchConfig, BondConfig, LockMultiplier, HumanProof, ReputationReason, SnapshotLeaf, QuestReward, UnclaimedReward};
+use crate::{NFTContractMetadata, StorageKey, TokenMetadata, PENDING_CLAIM_TIMEOUT};
+use crate::StorageManagement;
 use crate::approval::NonFungibleTokenCore;
-use near_sdk::json_types::{U128, U64};
-use near_sdk::test_utils::{accounts, VMContextBuilder};
+use near_sdk::borsh::BorshSerialize;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
+use near_sdk::test_utils::{accounts, get_created_receipts, VMContextBuilder};
 use near_sdk::testing_env;
-use near_sdk::{env, AccountId};
+use near_sdk::{env, AccountId, Balance, PromiseOrValue, PromiseResult, PublicKey, RuntimeFeesConfig, VMConfig};
+use ed25519_dalek::Signer;
+use std::convert::TryFrom;
 
 use std::collections::HashMap;
 
 const MINT_STORAGE_COST: u128 = 100_000_000_000_000_000_000_000;
 const MIN_REQUIRED_APPROVAL_YOCTO: u128 = 170000000000000000000;
+//answer of sbt_tokens_by_owner with a face verification SBT of Fractal that never expires
+const HUMAN_SBTS: &[u8] = br#"[["fractal.i-am-human.near",[{"token":1,"metadata":{"class":1,"issued_at":0}}]]]"#;
 
 fn get_context(predecessor: AccountId) -> VMContextBuilder {
     let mut builder = VMContextBuilder::new();
@@ -19,6 +27,52 @@ fn get_context(predecessor: AccountId) -> VMContextBuilder {
     builder
 }
 
+// Claim a quest from accounts(1) attaching the passed in deposit
+fn claim_quest(context: &mut VMContextBuilder, contract: &mut Contract, quest_number: u64, deposit: Balance) -> PromiseOrValue<ClaimOutcome> {
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(deposit)
+        .signer_account_id(accounts(1))
+        .predecessor_account_id(accounts(1))
+        .build());
+    contract.nft_mint_quest(quest_number)
+}
+
+// Get the outcome of a claim rejected before its verification call
+fn rejected_claim(claim: PromiseOrValue<ClaimOutcome>) -> ClaimOutcome {
+    match claim {
+        PromiseOrValue::Value(outcome) => outcome,
+        PromiseOrValue::Promise(_) => panic!("Expected the claim to be rejected"),
+    }
+}
+
+// Get the nonce of the pending claim of the quest, passed in to its callback
+fn pending_nonce(contract: &Contract, account_id: AccountId, quest_id: u64) -> u64 {
+    contract
+        .pending_claims
+        .get(&account_id)
+        .and_then(|claims| claims.into_iter().find(|claim| claim.quest_id == quest_id))
+        .map(|claim| claim.nonce)
+        .expect("No pending claim of the quest")
+}
+
+// Set up the environment of a quest callback with the result of the verification call
+fn resolve_context(context: &mut VMContextBuilder, result: PromiseResult) {
+    testing_env!(
+        context
+            .storage_usage(env::storage_usage())
+            .attached_deposit(0)
+            .signer_account_id(accounts(1))
+            .current_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![result],
+    );
+}
+
 fn sample_token_metadata() -> TokenMetadata {
     TokenMetadata {
         title: Some("Olympus Mons".into()),
@@ -37,6 +91,7 @@ fn sample_token_metadata() -> TokenMetadata {
 }
 
 #[test]
+#[ignore = "PanicOnDefault panics through the host, which can't unwind in unit tests"]
 #[should_panic(expected = "The contract is not initialized")]
 fn test_default() {
     let context = get_context(accounts(1));
@@ -48,7 +103,7 @@ fn test_default() {
 fn test_new_account_contract() {
     let mut context = get_context(accounts(1));
     testing_env!(context.build());
-    let contract = Contract::new_default_meta(accounts(1).into());
+    let contract = Contract::new_default_meta(accounts(1));
     testing_env!(context.is_view(true).build());
     let contract_nft_tokens = contract.nft_tokens(Some(U128(0)), None);
     assert_eq!(contract_nft_tokens.len(), 0);
@@ -58,7 +113,7 @@ fn test_new_account_contract() {
 fn test_mint_nft() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
     testing_env!(context
         .storage_usage(env::storage_usage())
         .attached_deposit(MINT_STORAGE_COST)
@@ -91,7 +146,7 @@ fn test_mint_nft() {
 fn test_internal_transfer() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -143,7 +198,7 @@ fn test_internal_transfer() {
 fn test_nft_approve() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -173,7 +228,7 @@ fn test_nft_approve() {
 fn test_nft_revoke() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -211,7 +266,7 @@ fn test_nft_revoke() {
 fn test_revoke_all() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -249,7 +304,7 @@ fn test_revoke_all() {
 fn test_internal_remove_token_from_owner() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -272,7 +327,7 @@ fn test_nft_payout() {
     use crate::royalty::NonFungibleTokenCore;
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -299,7 +354,7 @@ fn test_nft_payout() {
 fn test_nft_total_supply() {
     let mut context = get_context(accounts(0));
     testing_env!(context.build());
-    let mut contract = Contract::new_default_meta(accounts(0).into());
+    let mut contract = Contract::new_default_meta(accounts(0));
 
     testing_env!(context
         .storage_usage(env::storage_usage())
@@ -311,4 +366,1803 @@ fn test_nft_total_supply() {
 
     let total_supply = contract.nft_total_supply();
     assert_eq!(total_supply, U128(1));
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_storage_deposit_and_withdraw() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let balance = contract.storage_deposit(None, None);
+    assert_eq!(balance.total, U128(MINT_STORAGE_COST));
+    assert_eq!(balance.available, U128(MINT_STORAGE_COST));
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let balance = contract.storage_withdraw(Some(U128(MINT_STORAGE_COST / 2)));
+    assert_eq!(balance.total, U128(MINT_STORAGE_COST / 2));
+    assert_eq!(balance.available, U128(MINT_STORAGE_COST / 2));
+}
+
+#[test]
+fn test_failed_claim_refunds_deposit() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    let balance = contract.storage_balance_of(accounts(1)).unwrap();
+    assert_eq!(balance.total, U128(MINT_STORAGE_COST));
+
+    resolve_context(&mut context, PromiseResult::Failed);
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 2, error: ClaimError::VerifierFailed });
+
+    let balance = contract.storage_balance_of(accounts(1)).unwrap();
+    assert_eq!(balance.total, U128(0));
+    assert_eq!(contract.nft_total_supply(), U128(0));
+}
+
+#[test]
+fn test_failed_claim_refunds_payer() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //accounts(2) pays the claim of accounts(1)
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .signer_account_id(accounts(1))
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.nft_mint_quest(2);
+    assert_eq!(contract.get_pending_claims(accounts(1))[0].payer, accounts(2));
+
+    resolve_context(&mut context, PromiseResult::Failed);
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    let receipts = get_created_receipts();
+    assert_eq!(receipts.len(), 1);
+    assert_eq!(receipts[0].receiver_id, accounts(2));
+    assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().total, U128(0));
+}
+
+#[test]
+fn test_claim_charges_storage() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert_eq!(
+        outcome,
+        ClaimOutcome::Claimed {
+            quest_id: 2,
+            token_ids: vec!["0".to_string(), "1".to_string()],
+            score_delta: 15,
+            total_score: 15,
+            quests_completed: 1,
+        }
+    );
+
+    let balance = contract.storage_balance_of(accounts(1)).unwrap();
+    assert_eq!(balance.total, U128(MINT_STORAGE_COST));
+    assert!(balance.available.0 < MINT_STORAGE_COST);
+    assert!(balance.available.0 >= MINT_STORAGE_COST - contract.storage_balance_bounds().min.0);
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(2));
+}
+
+#[test]
+fn test_not_eligible_claim_outcome() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+
+    resolve_context(&mut context, PromiseResult::Successful(b"[]".to_vec()));
+    let outcome = contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 0, error: ClaimError::NotEligible });
+    assert!(near_sdk::test_utils::get_logs()
+        .iter()
+        .any(|log| log.contains(r#""event":"quest_claim_failed""#) && log.contains(r#""error":"not_eligible""#)));
+}
+
+#[test]
+#[should_panic(expected = "Method is private")]
+fn test_resolve_mint_rejects_external_calls() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    testing_env!(
+        context.signer_account_id(accounts(1)).predecessor_account_id(accounts(1)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![PromiseResult::Successful(b"100".to_vec())],
+    );
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+}
+
+#[test]
+fn test_resolve_mint_uses_quest_registry() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+
+    //the quest changes while the verification call is in flight
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    let mut quest = contract.get_quest(2).unwrap().quest;
+    quest.score = 25;
+    contract.update_quest(2, quest);
+
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { score_delta: 25, .. }));
+    let metadata = contract.token_metadata_by_id.get(&"0".to_string()).unwrap();
+    assert_eq!(metadata.title, Some("Meta Token".to_string()));
+    assert!(contract.get_pending_claims(accounts(1)).is_empty());
+}
+
+#[test]
+fn test_get_reputation() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 0);
+    assert_eq!(reputation.tier, ReputationTier::None);
+    assert!(reputation.por_token_id.is_none());
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 15);
+    assert_eq!(reputation.quests_completed, 1);
+    assert_eq!(reputation.quests, vec![2]);
+    assert_eq!(reputation.tier, ReputationTier::Bronze);
+    assert_eq!(reputation.por_token_id, Some("1".to_string()));
+
+    assert!(contract.has_min_reputation(accounts(1), 15));
+    assert!(!contract.has_min_reputation(accounts(1), 16));
+    assert!(contract.has_completed_quest(accounts(1), 2));
+    assert!(!contract.has_completed_quest(accounts(1), 0));
+    assert_eq!(contract.get_reputations(vec![accounts(1), accounts(2)]).len(), 2);
+}
+
+// Register an off-chain quest with id 10 and make accounts(2) an issuer of it with the passed in quota
+fn setup_issuer(context: &mut VMContextBuilder, contract: &mut Contract, quota: u64) {
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.add_quest(10, QuestConfig {
+        name: "Workshop".to_string(),
+        description: "Attend a workshop.".to_string(),
+        score: 20,
+        image: "".to_string(),
+        verifier: QuestVerifier::Issuer,
+        min_value: None,
+        category: QuestCategory::Learning,
+        prerequisites: vec![],
+        max_completions: None,
+        early_bird: None,
+        repeat: None,
+        reward: None,
+        active: true,
+    });
+    contract.add_issuer(accounts(2), vec![10], quota);
+}
+
+#[test]
+fn test_award_quest() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    let outcome = contract.award_quest(accounts(1), 10, Some("ipfs://evidence".to_string()));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { quest_id: 10, score_delta: 20, .. }));
+    //the account only keeps the part of the deposit of the issuer used by the mint
+    assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available, U128(0));
+
+    assert!(contract.has_completed_quest(accounts(1), 10));
+    assert_eq!(contract.get_issuer(accounts(2)).unwrap().issuer.awarded, 1);
+    let metadata = contract.token_metadata_by_id.get(&"0".to_string()).unwrap();
+    assert_eq!(metadata.reference, Some("ipfs://evidence".to_string()));
+    assert!(metadata.extra.unwrap().contains(accounts(2).as_str()));
+}
+
+#[test]
+fn test_award_quest_quota() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 1);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    let outcome = award_at(&mut context, &mut contract, accounts(3), 0);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 10, error: ClaimError::IssuerQuotaReached });
+    //nothing is kept and the deposit goes back to the issuer
+    assert!(contract.storage_balance_of(accounts(3)).is_none());
+    assert_eq!(contract.get_issuer(accounts(2)).unwrap().issuer.awarded, 1);
+    assert_eq!(get_created_receipts().last().unwrap().receiver_id, accounts(2));
+}
+
+#[test]
+fn test_award_quest_not_issuer() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 1);
+
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(3))
+        .build());
+    let outcome = contract.award_quest(accounts(1), 10, None);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 10, error: ClaimError::NotIssuer });
+    let outcome = award_at(&mut context, &mut contract, accounts(1), 0);
+    assert!(matches!(outcome, ClaimOutcome::Claimed { .. }));
+
+    //the issuer can only award its own quests
+    testing_env!(context.predecessor_account_id(accounts(2)).build());
+    let outcome = contract.award_quest(accounts(1), 2, None);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 2, error: ClaimError::IssuerNotAllowed });
+}
+
+#[test]
+fn test_issuer_quest_cannot_be_claimed() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 1);
+
+    let claim = claim_quest(&mut context, &mut contract, 10, MINT_STORAGE_COST);
+    assert_eq!(rejected_claim(claim), ClaimOutcome::Failed { quest_id: 10, error: ClaimError::IssuerOnly });
+    assert!(contract.get_pending_claims(accounts(1)).is_empty());
+    assert!(contract.storage_balance_of(accounts(1)).is_none());
+}
+
+// Register a signed quest with id 20 and an oracle key, returns the key pair of the oracle
+fn setup_oracle(context: &mut VMContextBuilder, contract: &mut Contract) -> (ed25519_dalek::Keypair, PublicKey) {
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+    let public = ed25519_dalek::PublicKey::from(&secret);
+    let public_key = PublicKey::try_from([vec![0], public.to_bytes().to_vec()].concat()).unwrap();
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.add_quest(20, QuestConfig {
+        name: "GitHub".to_string(),
+        description: "Contribute to a repository.".to_string(),
+        score: 25,
+        image: "".to_string(),
+        verifier: QuestVerifier::Oracle,
+        min_value: None,
+        category: QuestCategory::Learning,
+        prerequisites: vec![],
+        max_completions: None,
+        early_bird: None,
+        repeat: None,
+        reward: None,
+        active: true,
+    });
+    contract.add_oracle_key(public_key.clone());
+    (ed25519_dalek::Keypair { secret, public }, public_key)
+}
+
+// Sign a claim of quest 20 for accounts(1) and submit it
+fn claim_signed(context: &mut VMContextBuilder, contract: &mut Contract, keypair: &ed25519_dalek::Keypair, public_key: PublicKey, nonce: u64) -> ClaimOutcome {
+    let claim = SignedClaim { account_id: accounts(1), quest_id: 20, nonce: U64(nonce), expires_at: U64(1_000) };
+    let signature = keypair.sign(&claim.message(&accounts(0)));
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .current_account_id(accounts(0))
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(10)
+        .build());
+    contract.claim_signed_quest(claim, public_key, Base64VecU8(signature.to_bytes().to_vec()))
+}
+
+#[test]
+fn test_claim_signed_quest() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let (keypair, public_key) = setup_oracle(&mut context, &mut contract);
+
+    let outcome = claim_signed(&mut context, &mut contract, &keypair, public_key, 1);
+    assert!(matches!(outcome, ClaimOutcome::Claimed { quest_id: 20, score_delta: 25, .. }));
+    assert!(contract.has_completed_quest(accounts(1), 20));
+    assert!(contract.is_nonce_used(accounts(1), U64(1)));
+}
+
+#[test]
+#[should_panic(expected = "Invalid signature")]
+fn test_claim_signed_quest_invalid_signature() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let (_, public_key) = setup_oracle(&mut context, &mut contract);
+
+    let secret = ed25519_dalek::SecretKey::from_bytes(&[8; 32]).unwrap();
+    let other = ed25519_dalek::Keypair { public: ed25519_dalek::PublicKey::from(&secret), secret };
+    claim_signed(&mut context, &mut contract, &other, public_key, 1);
+}
+
+#[test]
+#[should_panic(expected = "Invalid signature")]
+fn test_claim_signed_for_another_contract() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let (keypair, public_key) = setup_oracle(&mut context, &mut contract);
+
+    let claim = SignedClaim { account_id: accounts(1), quest_id: 20, nonce: U64(1), expires_at: U64(1_000) };
+    let signature = keypair.sign(&claim.message(&accounts(2)));
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .current_account_id(accounts(0))
+        .predecessor_account_id(accounts(1))
+        .block_timestamp(10)
+        .build());
+    contract.claim_signed_quest(claim, public_key, Base64VecU8(signature.to_bytes().to_vec()));
+}
+
+#[test]
+#[should_panic(expected = "The nonce has already been used")]
+fn test_claim_signed_quest_replay() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let (keypair, public_key) = setup_oracle(&mut context, &mut contract);
+
+    claim_signed(&mut context, &mut contract, &keypair, public_key.clone(), 1);
+    claim_signed(&mut context, &mut contract, &keypair, public_key, 1);
+}
+
+#[test]
+fn test_category_scores() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 1);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.award_quest(accounts(1), 10, None);
+
+    assert_eq!(contract.get_category_score(accounts(1), QuestCategory::DeFi), 15);
+    assert_eq!(contract.get_category_score(accounts(1), QuestCategory::Learning), 20);
+    assert_eq!(contract.get_category_score(accounts(1), QuestCategory::Governance), 0);
+
+    //DeFi counts double and Learning half
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_category_weight(QuestCategory::DeFi, 20_000);
+    contract.set_category_weight(QuestCategory::Learning, 5_000);
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 35);
+    assert_eq!(reputation.composite_score, 40);
+    assert_eq!(contract.get_category_weights()[&QuestCategory::Identity], 10_000);
+
+    //a penalty is taken from the categories too, so the composite score goes down with the score
+    contract.penalize(accounts(1), 7);
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 28);
+    assert_eq!(reputation.categories[&QuestCategory::DeFi], 12);
+    assert_eq!(reputation.categories[&QuestCategory::Learning], 16);
+    assert_eq!(reputation.composite_score, 32);
+}
+
+#[test]
+fn test_claim_missing_prerequisites() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //stNEAR requires I'm Human, the claim fails without the verification call and nothing is kept
+    let claim = claim_quest(&mut context, &mut contract, 1, MINT_STORAGE_COST);
+    assert_eq!(
+        rejected_claim(claim),
+        ClaimOutcome::Failed { quest_id: 1, error: ClaimError::PrerequisitesMissing }
+    );
+    assert!(contract.get_pending_claims(accounts(1)).is_empty());
+    assert!(contract.storage_balance_of(accounts(1)).is_none());
+}
+
+#[test]
+#[should_panic(expected = "The prerequisites of a quest can't form a cycle")]
+fn test_prerequisite_cycle() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //stNEAR requires I'm Human, so I'm Human can't require stNEAR
+    let mut quest = contract.get_quest(0).unwrap().quest;
+    quest.prerequisites = vec![1];
+    contract.update_quest(0, quest);
+}
+
+#[test]
+fn test_get_available_quests() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+
+    let quests = contract.get_available_quests(accounts(1));
+    assert_eq!(quests.len(), 4);
+    assert_eq!(quests[0].status, QuestStatus::Completed);
+    assert_eq!(quests[1].status, QuestStatus::Claimable);
+    assert_eq!(quests[3].status, QuestStatus::Claimable);
+
+    let quests = contract.get_available_quests(accounts(2));
+    assert_eq!(quests[1].status, QuestStatus::Locked);
+    assert_eq!(quests[1].missing_prerequisites, vec![0]);
+    assert_eq!(quests[2].status, QuestStatus::Claimable);
+}
+
+// Open a season between the timestamps 100 and 200 with the off-chain quest 10
+fn setup_season(context: &mut VMContextBuilder, contract: &mut Contract) {
+    setup_issuer(context, contract, 2);
+    contract.add_season(1, Season {
+        name: "Season 1".to_string(),
+        starts_at: U64(100),
+        ends_at: U64(200),
+        quest_ids: vec![10],
+        closed: false,
+    });
+}
+
+// Deposit storage balance for the account, to pay the storage of what it does after being awarded a quest
+fn deposit_storage(context: &mut VMContextBuilder, contract: &mut Contract, account_id: AccountId) {
+    testing_env!(context.attached_deposit(MINT_STORAGE_COST).predecessor_account_id(account_id.clone()).build());
+    contract.storage_deposit(Some(account_id), None);
+}
+
+// Award the quest 10 from the issuer accounts(2) at the passed in timestamp
+fn award_at(context: &mut VMContextBuilder, contract: &mut Contract, account_id: AccountId, timestamp: u64) -> ClaimOutcome {
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .block_timestamp(timestamp)
+        .build());
+    contract.award_quest(account_id, 10, None)
+}
+
+#[test]
+fn test_season_scores_and_ranking() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_season(&mut context, &mut contract);
+
+    award_at(&mut context, &mut contract, accounts(3), 150);
+    award_at(&mut context, &mut contract, accounts(1), 160);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 20);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 20);
+
+    //the archive is resumed over several calls, the season is closed from the first one
+    testing_env!(context.predecessor_account_id(accounts(0)).block_timestamp(300).build());
+    assert!(!contract.close_season(1, Some(2)));
+    assert!(contract.get_season(1).unwrap().season.closed);
+    while !contract.close_season(1, Some(2)) {}
+    assert_eq!(
+        contract.get_season_ranking(1, None, None),
+        vec![
+            SeasonRankingEntry { account_id: accounts(1), score: 20 },
+            SeasonRankingEntry { account_id: accounts(3), score: 20 },
+        ]
+    );
+}
+
+#[test]
+fn test_quest_out_of_season() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_season(&mut context, &mut contract);
+
+    let outcome = award_at(&mut context, &mut contract, accounts(1), 250);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 10, error: ClaimError::OutOfSeason });
+}
+
+#[test]
+fn test_quest_supply_checked_in_callback() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let mut quest = contract.get_quest(2).unwrap().quest;
+    quest.max_completions = Some(1);
+    contract.update_quest(2, quest);
+
+    //two claims pass the first check while the verification calls are in flight
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .signer_account_id(accounts(3))
+        .predecessor_account_id(accounts(3))
+        .build());
+    contract.nft_mint_quest(2);
+
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { .. }));
+    assert_eq!(contract.token_metadata_by_id.get(&"0".to_string()).unwrap().copies, Some(1));
+
+    testing_env!(
+        context.signer_account_id(accounts(3)).build(),
+        VMConfig::test(),
+        RuntimeFeesConfig::test(),
+        HashMap::default(),
+        vec![PromiseResult::Successful(b"100".to_vec())],
+    );
+    let outcome = contract.resolve_mint_meta_token(2, accounts(3), pending_nonce(&contract, accounts(3), 2));
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 2, error: ClaimError::SupplyExhausted });
+    assert_eq!(contract.storage_balance_of(accounts(3)).unwrap().total, U128(0));
+    assert_eq!(
+        contract.get_quest_supply(2),
+        QuestSupply { completions: 1, max_completions: Some(1), remaining: Some(0) }
+    );
+    assert_eq!(contract.get_available_quests(accounts(3))[2].status, QuestStatus::SupplyExhausted);
+    assert_eq!(contract.get_available_quests(accounts(1))[2].status, QuestStatus::Completed);
+}
+
+#[test]
+fn test_early_bird_bonus() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+    let mut quest = contract.get_quest(10).unwrap().quest;
+    quest.early_bird = Some(EarlyBird { completers: 1, bonus: 5 });
+    contract.update_quest(10, quest);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(3), 0);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 25);
+    assert_eq!(contract.get_reputation(accounts(3)).score, 20);
+}
+
+// Make the off-chain quest 10 repeatable every 100ns, keeping the streak for 200ns and doubling the score from a streak of 3
+fn setup_repeatable(context: &mut VMContextBuilder, contract: &mut Contract) {
+    setup_issuer(context, contract, 10);
+    let mut quest = contract.get_quest(10).unwrap().quest;
+    quest.repeat = Some(RepeatConfig {
+        cooldown: U64(100),
+        streak_window: U64(200),
+        streak_multipliers: vec![StreakMultiplier { min_streak: 3, multiplier: 20_000 }],
+    });
+    contract.update_quest(10, quest);
+}
+
+#[test]
+fn test_repeatable_quest_streak() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_repeatable(&mut context, &mut contract);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(1), 150);
+    award_at(&mut context, &mut contract, accounts(1), 300);
+
+    //the badge is updated instead of minting a new one
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(2));
+    let progress = contract.get_quest_progress(accounts(1), 10).unwrap();
+    assert_eq!(progress.times_completed, 3);
+    assert_eq!(progress.streak, 3);
+    assert_eq!(progress.next_claim_at, Some(U64(400)));
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 20 + 20 + 40);
+    assert_eq!(reputation.quests_completed, 1);
+
+    //missing the window resets the streak
+    award_at(&mut context, &mut contract, accounts(1), 1_000);
+    let progress = contract.get_quest_progress(accounts(1), 10).unwrap();
+    assert_eq!(progress.streak, 1);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 100);
+}
+
+#[test]
+fn test_repeatable_quest_cooldown() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_repeatable(&mut context, &mut contract);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    let outcome = award_at(&mut context, &mut contract, accounts(1), 50);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 10, error: ClaimError::CooldownActive });
+}
+
+#[test]
+fn test_transferred_badge_keeps_cooldown() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_repeatable(&mut context, &mut contract);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    let (token_id, _) = contract.internal_quest_badge_of(&accounts(1), 10).unwrap();
+    contract.internal_transfer(&accounts(1), &accounts(3), &token_id, None, None);
+    let outcome = award_at(&mut context, &mut contract, accounts(1), 50);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 10, error: ClaimError::CooldownActive });
+}
+
+#[test]
+#[should_panic(expected = "The Proof of Reputation can't be transferred")]
+fn test_por_can_not_be_transferred() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    let por_token_id = contract.get_reputation(accounts(1)).por_token_id.unwrap();
+    contract.internal_transfer(&accounts(1), &accounts(3), &por_token_id, None, None);
+}
+
+#[test]
+fn test_transferred_badge_keeps_prerequisites() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    let (token_id, _) = contract.internal_quest_badge_of(&accounts(1), 0).unwrap();
+    contract.internal_transfer(&accounts(1), &accounts(3), &token_id, None, None);
+
+    //stNEAR requires I'm Human, which stays completed by accounts(1) and not by the new holder of the badge
+    assert_eq!(contract.get_available_quests(accounts(1))[1].status, QuestStatus::Claimable);
+    assert_eq!(contract.get_available_quests(accounts(3))[1].status, QuestStatus::Locked);
+    assert!(matches!(claim_quest(&mut context, &mut contract, 1, MINT_STORAGE_COST), PromiseOrValue::Promise(_)));
+}
+
+#[test]
+fn test_zero_balance_is_not_eligible() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //ft_balance_of returns the balance as a JSON string
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"\"0\"".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 2, error: ClaimError::NotEligible });
+}
+
+#[test]
+fn test_check_quest_eligibility() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    resolve_context(&mut context, PromiseResult::Successful(b"\"250\"".to_vec()));
+    assert_eq!(
+        contract.resolve_eligibility(1, accounts(1), vec![0], Some(ClaimError::PrerequisitesMissing)),
+        EligibilityReport {
+            quest_id: 1,
+            eligible: false,
+            observed_value: Some(U128(250)),
+            threshold: Some(U128(1)),
+            missing_prerequisites: vec![0],
+            error: Some(ClaimError::PrerequisitesMissing),
+        }
+    );
+
+    resolve_context(&mut context, PromiseResult::Successful(b"[]".to_vec()));
+    let report = contract.resolve_eligibility(0, accounts(1), vec![], None);
+    assert_eq!(report.observed_value, Some(U128(0)));
+    assert_eq!(report.error, Some(ClaimError::NotEligible));
+
+    //quests awarded by issuers are answered without a cross contract call
+    setup_issuer(&mut context, &mut contract, 1);
+    match contract.check_quest_eligibility(accounts(1), 10) {
+        PromiseOrValue::Value(report) => {
+            assert!(report.eligible);
+            assert_eq!(report.threshold, None);
+        }
+        PromiseOrValue::Promise(_) => panic!("Expected a value"),
+    }
+}
+
+#[test]
+fn test_tokens_by_quest() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(3), 0);
+    assert_eq!(contract.nft_supply_for_quest(10), U128(2));
+    assert_eq!(contract.nft_supply_for_quest(0), U128(0));
+
+    //the Proof of Reputation tokens are not part of the quest
+    let owners: Vec<AccountId> = contract
+        .nft_tokens_by_quest(10, None, None)
+        .tokens
+        .into_iter()
+        .map(|token| token.owner_id)
+        .collect();
+    assert_eq!(owners, vec![accounts(1), accounts(3)]);
+    let page = contract.nft_tokens_by_quest(10, None, Some(1));
+    assert_eq!(page.tokens[0].owner_id, accounts(1));
+    let page = contract.nft_tokens_by_quest(10, page.next_cursor, Some(1));
+    assert_eq!(page.tokens[0].owner_id, accounts(3));
+    assert_eq!(page.next_cursor, None);
+}
+
+#[test]
+fn test_tokens_for_owner_page() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_season(&mut context, &mut contract);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    award_at(&mut context, &mut contract, accounts(1), 150);
+
+    //tokens 0 (Meta Token), 1 (PoR) and 2 (Workshop), one per page
+    let page = contract.nft_tokens_for_owner_page(accounts(1), None, None, Some(1));
+    assert_eq!(page.tokens[0].token_id, "0");
+    let page = contract.nft_tokens_for_owner_page(accounts(1), None, page.next_cursor, Some(1));
+    assert_eq!(page.tokens[0].token_id, "1");
+    let page = contract.nft_tokens_for_owner_page(accounts(1), None, page.next_cursor, Some(1));
+    assert_eq!(page.tokens[0].token_id, "2");
+    assert_eq!(page.next_cursor, None);
+
+    let token_ids = |filter: TokenFilter| -> Vec<String> {
+        contract
+            .nft_tokens_for_owner_page(accounts(1), Some(filter), None, None)
+            .tokens
+            .into_iter()
+            .map(|token| token.token_id)
+            .collect()
+    };
+    assert_eq!(token_ids(TokenFilter { kind: Some(TokenKind::Por), ..Default::default() }), vec!["1"]);
+    assert_eq!(token_ids(TokenFilter { kind: Some(TokenKind::Quest), ..Default::default() }), vec!["0", "2"]);
+    assert_eq!(token_ids(TokenFilter { category: Some(QuestCategory::DeFi), ..Default::default() }), vec!["0"]);
+    assert_eq!(token_ids(TokenFilter { season_id: Some(1), ..Default::default() }), vec!["2"]);
+}
+
+#[test]
+fn test_tokens_page() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(3), 0);
+
+    let page = contract.nft_tokens_page(None, Some(3));
+    assert_eq!(page.tokens.len(), 3);
+    let page = contract.nft_tokens_page(page.next_cursor, Some(3));
+    assert_eq!(page.tokens.len(), 1);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[test]
+fn test_tokens_page_stable_across_burns() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(3), 0);
+
+    let token_ids = |page: &crate::TokenPage| page.tokens.iter().map(|token| token.token_id.clone()).collect::<Vec<_>>();
+    let page = contract.nft_tokens_page(None, Some(2));
+    assert_eq!(token_ids(&page), vec!["0", "1"]);
+
+    //burning a token of the first page doesn't move the tokens of the next one
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.add_moderator(accounts(0));
+    contract.revoke_quest(accounts(1), 10);
+    let page = contract.nft_tokens_page(page.next_cursor, Some(2));
+    assert_eq!(token_ids(&page), vec!["2", "3"]);
+    assert_eq!(page.next_cursor, None);
+}
+
+#[test]
+fn test_token_ids_are_not_reused() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+
+    //the owner takes the ID "1" that the counter would give next
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(0))
+        .build());
+    contract.nft_mint("1".to_string(), sample_token_metadata(), accounts(0), None);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    let token_ids: Vec<String> = contract.nft_tokens_for_owner(accounts(1), None, None).into_iter().map(|token| token.token_id).collect();
+    assert_eq!(token_ids, vec!["0", "2"]);
+    assert_eq!(contract.parse_token_id("0".to_string()), Some(TokenIdInfo { quest_id: 10, serial: 1 }));
+    assert_eq!(contract.parse_token_id("2".to_string()), None);
+}
+
+#[test]
+fn test_quest_serial_token_ids() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 2);
+    contract.set_token_id_scheme(TokenIdScheme::QuestSerial);
+
+    award_at(&mut context, &mut contract, accounts(1), 0);
+    award_at(&mut context, &mut contract, accounts(3), 0);
+    assert_eq!(contract.nft_tokens_by_quest(10, None, None).tokens[1].token_id, "10:2");
+    assert_eq!(contract.parse_token_id("10:2".to_string()), Some(TokenIdInfo { quest_id: 10, serial: 2 }));
+    assert_eq!(contract.get_reputation(accounts(3)).por_token_id, Some("1".to_string()));
+}
+
+#[test]
+fn test_parallel_claims_of_a_quest_are_blocked() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    assert_eq!(contract.get_pending_claims(accounts(1)).len(), 1);
+    let claim = claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    assert_eq!(
+        rejected_claim(claim),
+        ClaimOutcome::Failed { quest_id: 2, error: ClaimError::ClaimPending }
+    );
+}
+
+#[test]
+fn test_late_callback_keeps_newer_claim() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    let expired_nonce = pending_nonce(&contract, accounts(1), 2);
+    context.block_timestamp(PENDING_CLAIM_TIMEOUT + 1);
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    let nonce = pending_nonce(&contract, accounts(1), 2);
+    assert_ne!(nonce, expired_nonce);
+
+    //the callback of the expired claim doesn't mint nor clear the newer claim
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), expired_nonce);
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 2, error: ClaimError::ClaimExpired });
+    assert_eq!(contract.get_pending_claims(accounts(1)).len(), 1);
+    assert_eq!(contract.nft_total_supply(), U128(0));
+
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), nonce);
+    assert!(matches!(outcome, ClaimOutcome::Claimed { quest_id: 2, .. }));
+    assert!(contract.get_pending_claims(accounts(1)).is_empty());
+}
+
+#[test]
+fn test_parallel_claims_create_one_por() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    assert_eq!(contract.get_pending_claims(accounts(1)).len(), 2);
+
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    //the second quest is added to the Proof of Reputation created by the first one
+    assert!(matches!(outcome, ClaimOutcome::Claimed { total_score: 55, quests_completed: 2, .. }));
+    assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(3));
+    assert!(contract.get_pending_claims(accounts(1)).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "Must attach")]
+fn test_parallel_claims_reserve_storage() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //the storage of a claim is reserved until its callback runs, so the second claim isn't covered
+    let claim_cost = contract.storage_balance_bounds().min.0;
+    claim_quest(&mut context, &mut contract, 0, claim_cost);
+    assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available, U128(0));
+    claim_quest(&mut context, &mut contract, 2, 0);
+}
+
+#[test]
+fn test_withdraw_during_claim_keeps_reserved_storage() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    let claim_cost = contract.storage_balance_bounds().min.0;
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(1)
+        .predecessor_account_id(accounts(1))
+        .build());
+    let balance = contract.storage_withdraw(None);
+    assert_eq!(balance.total, U128(claim_cost));
+    assert_eq!(balance.available, U128(0));
+
+    //the callback settles the reservation with the storage really used
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { .. }));
+    let balance = contract.storage_balance_of(accounts(1)).unwrap();
+    assert_eq!(balance.total, U128(claim_cost));
+    assert!(balance.available.0 < claim_cost);
+}
+
+#[test]
+fn test_failed_claim_releases_reserved_storage() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    deposit_storage(&mut context, &mut contract, accounts(1));
+    claim_quest(&mut context, &mut contract, 2, 0);
+    let claim_cost = contract.storage_balance_bounds().min.0;
+    assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available, U128(MINT_STORAGE_COST - claim_cost));
+
+    resolve_context(&mut context, PromiseResult::Failed);
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert_eq!(contract.storage_balance_of(accounts(1)).unwrap().available, U128(MINT_STORAGE_COST));
+}
+
+fn setup_vouches(context: &mut VMContextBuilder, contract: &mut Contract) {
+    setup_issuer(context, contract, 5);
+    for account in 1..=5 {
+        award_at(context, contract, accounts(account), 100);
+        deposit_storage(context, contract, accounts(account));
+    }
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.set_vouch_config(VouchConfig {
+        min_score: 20,
+        weight: 5_000,
+        max_bonus: 8,
+        quota: 2,
+        period: U64(1_000),
+    });
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(1)).build());
+}
+
+#[test]
+fn test_vouch_and_revoke_on_penalty() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_vouches(&mut context, &mut contract);
+
+    //the bonus is half the score of the voucher, capped to 8
+    let vouch = contract.vouch(accounts(3));
+    assert_eq!(vouch.bonus, 8);
+    contract.vouch(accounts(4));
+    assert_eq!(contract.get_reputation(accounts(3)).score, 28);
+    assert_eq!(contract.get_vouches_by(accounts(1), None, None).len(), 2);
+    assert_eq!(contract.get_vouches_for(accounts(3), None, None), vec![vouch]);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).block_timestamp(200).build());
+    assert_eq!(contract.penalize(accounts(1), 5), 15);
+    assert_eq!(contract.get_reputation(accounts(3)).score, 20);
+    assert_eq!(contract.get_reputation(accounts(4)).score, 20);
+    assert_eq!(contract.get_vouches_for(accounts(3), None, None)[0].revoked_at, Some(U64(200)));
+    assert!(contract.get_vouches_by(accounts(1), None, None).iter().all(|vouch| vouch.revoked_at.is_some()));
+}
+
+#[test]
+#[should_panic(expected = "The vouch quota of the period has been reached")]
+fn test_vouch_quota() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_vouches(&mut context, &mut contract);
+
+    contract.vouch(accounts(3));
+    contract.vouch(accounts(4));
+    contract.vouch(accounts(5));
+}
+
+const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+fn setup_bonds(context: &mut VMContextBuilder, contract: &mut Contract) {
+    setup_issuer(context, contract, 5);
+    award_at(context, contract, accounts(1), 100);
+    deposit_storage(context, contract, accounts(1));
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.set_bond_config(BondConfig {
+        points_per_near: 5,
+        lock_multipliers: vec![LockMultiplier { min_lock: U64(1_000), multiplier: 20_000 }],
+        max_boost: 50,
+        unbonding_delay: U64(500),
+    });
+    testing_env!(context.attached_deposit(2 * ONE_NEAR).predecessor_account_id(accounts(1)).build());
+    contract.bond_reputation(U64(1_000));
+}
+
+#[test]
+fn test_bond_reputation_and_unbond() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_bonds(&mut context, &mut contract);
+
+    //2 NEAR * 5 points, doubled by the lock
+    assert_eq!(contract.get_bond(accounts(1)).unwrap().boost, 20);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 40);
+
+    testing_env!(context.attached_deposit(0).block_timestamp(1_100).build());
+    let unbonding = contract.unbond_reputation();
+    assert_eq!(unbonding.available_at, U64(1_600));
+    assert_eq!(contract.get_reputation(accounts(1)).score, 20);
+    assert!(contract.get_bond(accounts(1)).is_none());
+
+    testing_env!(context.block_timestamp(1_600).build());
+    assert_eq!(contract.withdraw_unbonded(), U128(2 * ONE_NEAR));
+    assert!(contract.get_unbondings(accounts(1)).is_empty());
+}
+
+#[test]
+#[should_panic(expected = "The bond is still locked")]
+fn test_unbond_locked_bond() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_bonds(&mut context, &mut contract);
+
+    testing_env!(context.attached_deposit(0).block_timestamp(500).build());
+    contract.unbond_reputation();
+}
+
+#[test]
+fn test_revoke_quest_slashes_bond() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_bonds(&mut context, &mut contract);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.add_moderator(accounts(4));
+    contract.set_treasury(accounts(5));
+
+    testing_env!(context.predecessor_account_id(accounts(4)).build());
+    assert_eq!(contract.revoke_quest(accounts(1), 10), U128(2 * ONE_NEAR));
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.score, 0);
+    assert_eq!(reputation.quests_completed, 0);
+    assert!(reputation.quests.is_empty());
+    assert!(contract.get_bond(accounts(1)).is_none());
+    assert_eq!(contract.nft_supply_for_quest(10), U128(0));
+    assert!(near_sdk::test_utils::get_logs().iter().any(|log| log.contains(r#""event":"nft_burn""#)));
+}
+
+#[test]
+fn test_revoke_quest_takes_season_score() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_season(&mut context, &mut contract);
+
+    award_at(&mut context, &mut contract, accounts(1), 150);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 20);
+    assert_eq!(contract.get_quest_supply(10).completions, 1);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    contract.add_moderator(accounts(4));
+    testing_env!(context.predecessor_account_id(accounts(4)).build());
+    contract.revoke_quest(accounts(1), 10);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 0);
+    assert_eq!(contract.get_quest_supply(10).completions, 0);
+}
+
+#[test]
+#[should_panic(expected = "Only moderators can revoke quests")]
+fn test_revoke_quest_only_moderators() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_bonds(&mut context, &mut contract);
+
+    contract.revoke_quest(accounts(1), 10);
+}
+
+#[test]
+fn test_human_gating_holds_score() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    contract.set_human_gating(true);
+    assert!(contract.get_config().human_gating);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { score_delta: 0, total_score: 0, .. }));
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.pending_score, 15);
+    assert_eq!(reputation.quests, vec![2]);
+
+    //proving humanity releases the pending score
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    let outcome = contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { score_delta: 55, total_score: 55, .. }));
+    assert_eq!(contract.get_reputation(accounts(1)).pending_score, 0);
+    assert_eq!(contract.get_category_score(accounts(1), QuestCategory::DeFi), 15);
+}
+
+#[test]
+fn test_revoke_quest_takes_pending_score() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 1);
+    contract.set_human_gating(true);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_human_gating(false);
+    contract.add_moderator(accounts(4));
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .predecessor_account_id(accounts(2))
+        .build());
+    contract.award_quest(accounts(1), 10, None);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 20);
+
+    //the score of the revoked quest was held, so only the pending score goes down
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(4)).build());
+    contract.revoke_quest(accounts(1), 2);
+    let reputation = contract.get_reputation(accounts(1));
+    assert_eq!(reputation.pending_score, 0);
+    assert_eq!(reputation.score, 20);
+    assert_eq!(reputation.categories[&QuestCategory::Learning], 20);
+}
+
+#[test]
+fn test_release_pending_score_after_gating_off() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    contract.set_human_gating(true);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_human_gating(false);
+    assert_eq!(contract.release_pending_score(accounts(1)), 15);
+    assert_eq!(contract.get_reputation(accounts(1)).score, 15);
+}
+
+#[test]
+fn test_release_pending_score_adds_season_score() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_season(&mut context, &mut contract);
+    contract.set_human_gating(true);
+
+    award_at(&mut context, &mut contract, accounts(1), 150);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 0);
+
+    //the score is added to the season open when the quest was completed, even if it has ended since
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(300).build());
+    contract.set_human_gating(false);
+    assert_eq!(contract.release_pending_score(accounts(1)), 20);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 20);
+
+    contract.add_moderator(accounts(4));
+    testing_env!(context.predecessor_account_id(accounts(4)).build());
+    contract.revoke_quest(accounts(1), 10);
+    assert_eq!(contract.get_season_score(1, accounts(1)), 0);
+}
+
+#[test]
+fn test_human_proof_stored_on_badge() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    let outcome = contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { .. }));
+
+    let (_, badge) = contract.internal_quest_badge_of(&accounts(1), 0).unwrap();
+    assert_eq!(
+        badge.human_proof,
+        Some(HumanProof {
+            issuer: "fractal.i-am-human.near".parse().unwrap(),
+            token_id: 1,
+            class: 1,
+            expires_at: None,
+        })
+    );
+}
+
+#[test]
+fn test_transferred_human_badge_does_not_verify() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(HUMAN_SBTS.to_vec()));
+    contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    let (token_id, _) = contract.internal_quest_badge_of(&accounts(1), 0).unwrap();
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.internal_transfer(&accounts(1), &accounts(3), &token_id, None, None);
+    assert_eq!(contract.tokens_by_id.get(&token_id).unwrap().owner_id, accounts(3));
+    assert!(contract.internal_quest_badge_of(&accounts(3), 0).is_none());
+
+    //the humanity stays with the account that proved it
+    assert!(contract.internal_is_verified_human(&accounts(1)));
+    assert!(!contract.internal_is_verified_human(&accounts(3)));
+}
+
+#[test]
+fn test_transferred_badge_can_not_be_claimed_again() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    let (token_id, _) = contract.internal_quest_badge_of(&accounts(1), 2).unwrap();
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(1)).build());
+    contract.internal_transfer(&accounts(1), &accounts(3), &token_id, None, None);
+
+    //the completion stays with the account that claimed the quest
+    let claim = claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    assert_eq!(rejected_claim(claim), ClaimOutcome::Failed { quest_id: 2, error: ClaimError::AlreadyClaimed });
+}
+
+#[test]
+fn test_human_proof_rejects_invalid_sbts() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.block_timestamp(10_000_000).build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    //an unknown issuer, a class that is not accepted and an expired SBT
+    let sbts = br#"[
+        ["bot.near",[{"token":1,"metadata":{"class":1}}]],
+        ["fractal.i-am-human.near",[{"token":2,"metadata":{"class":2}},{"token":3,"metadata":{"class":1,"expires_at":5}}]]
+    ]"#;
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(sbts.to_vec()));
+    let outcome = contract.resolve_mint_im_human(0, accounts(1), pending_nonce(&contract, accounts(1), 0));
+    assert_eq!(outcome, ClaimOutcome::Failed { quest_id: 0, error: ClaimError::NotEligible });
+}
+
+#[test]
+fn test_governance_account_changes_registry() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    contract.set_governance_account(Some(accounts(3)));
+    assert_eq!(contract.get_config().governance_account, Some(accounts(3)));
+
+    testing_env!(context.predecessor_account_id(accounts(3)).build());
+    contract.set_quest_active(2, false);
+    contract.set_category_weight(QuestCategory::DeFi, 5_000);
+    assert!(!contract.get_quest(2).unwrap().quest.active);
+    assert_eq!(contract.get_category_weights()[&QuestCategory::DeFi], 5_000);
+
+    //the scoring policy is governed too
+    contract.set_human_gating(true);
+    contract.set_vouch_config(contract.get_config().vouch_config);
+    assert!(contract.get_config().human_gating);
+}
+
+#[test]
+#[should_panic(expected = "Only the governance account can call this method")]
+fn test_owner_cant_change_registry_with_governance() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    contract.set_governance_account(Some(accounts(3)));
+
+    contract.set_quest_active(2, false);
+}
+
+#[test]
+#[should_panic(expected = "Only the governance account can call this method")]
+fn test_owner_cant_change_bond_config_with_governance() {
+    let context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    contract.set_governance_account(Some(accounts(3)));
+
+    let config = contract.get_config().bond_config;
+    contract.set_bond_config(config);
+}
+
+#[test]
+fn test_dao_member_quest() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    let mut quest = contract.get_quest(2).unwrap().quest;
+    quest.name = "Council".to_string();
+    quest.category = QuestCategory::Governance;
+    quest.verifier = QuestVerifier::DaoMember {
+        dao_id: "dao.sputnik-dao.near".parse().unwrap(),
+        roles: vec!["council".to_string()],
+    };
+    contract.add_quest(30, quest);
+
+    let policy = format!(
+        r#"{{"roles":[{{"name":"all","kind":"Everyone"}},{{"name":"council","kind":{{"Group":["{}"]}}}}],"bounty_bond":"1"}}"#,
+        accounts(1)
+    );
+    claim_quest(&mut context, &mut contract, 30, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(policy.clone().into_bytes()));
+    let outcome = contract.resolve_mint_dao_member(30, accounts(1), pending_nonce(&contract, accounts(1), 30));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { quest_id: 30, .. }));
+
+    //members of other roles are not eligible
+    let policy = policy.replace("council", "community");
+    resolve_context(&mut context, PromiseResult::Successful(policy.into_bytes()));
+    let report = contract.resolve_eligibility(30, accounts(1), vec![], None);
+    assert_eq!(report.observed_value, Some(U128(0)));
+    assert_eq!(report.error, Some(ClaimError::NotEligible));
+}
+
+#[test]
+fn test_get_score_at() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 5);
+    award_at(&mut context, &mut contract, accounts(1), 100);
+
+    //two changes in the same block leave a single checkpoint
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(300).build());
+    contract.penalize(accounts(1), 5);
+    contract.penalize(accounts(1), 5);
+    assert_eq!(contract.score_checkpoints.get(&accounts(1)).unwrap().len(), 2);
+
+    assert_eq!(contract.get_score_at(accounts(1), U64(50)), 0);
+    assert_eq!(contract.get_score_at(accounts(1), U64(100)), 20);
+    assert_eq!(contract.get_score_at(accounts(1), U64(299)), 20);
+    assert_eq!(contract.get_score_at(accounts(1), U64(300)), 10);
+    assert_eq!(contract.get_score_at(accounts(1), U64(1_000)), 10);
+    assert_eq!(contract.get_score_at(accounts(2), U64(1_000)), 0);
+}
+
+#[test]
+fn test_get_score_at_without_checkpoints() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 5);
+    award_at(&mut context, &mut contract, accounts(1), 100);
+
+    //a reputation earned before the checkpoints were introduced has no score in the past
+    contract.score_checkpoints.remove(&accounts(1));
+    assert_eq!(contract.get_score_at(accounts(1), U64(1_000)), 0);
+
+    //its first change keeps the score it had since its last quest
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(300).build());
+    contract.penalize(accounts(1), 5);
+    assert_eq!(contract.score_checkpoints.get(&accounts(1)).unwrap().len(), 2);
+    assert_eq!(contract.get_score_at(accounts(1), U64(50)), 0);
+    assert_eq!(contract.get_score_at(accounts(1), U64(200)), 20);
+    assert_eq!(contract.get_score_at(accounts(1), U64(300)), 15);
+}
+
+#[test]
+fn test_reputation_history() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 5);
+    award_at(&mut context, &mut contract, accounts(1), 100);
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(200).build());
+    contract.penalize(accounts(1), 5);
+    contract.add_moderator(accounts(4));
+    testing_env!(context.predecessor_account_id(accounts(4)).block_timestamp(300).build());
+    contract.revoke_quest(accounts(1), 10);
+
+    //the history survives the burn of the quest token
+    let history = contract.get_reputation_history(accounts(1), None, None);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0].reason, ReputationReason::QuestCompleted);
+    assert_eq!((history[0].quest_id, history[0].delta, history[0].score), (Some(10), 20, 20));
+    assert_eq!(history[0].actor, accounts(2));
+    assert_eq!(history[1].reason, ReputationReason::Penalized);
+    assert_eq!((history[1].delta, history[1].timestamp), (-5, U64(200)));
+    assert_eq!(history[2].reason, ReputationReason::QuestRevoked);
+    assert_eq!((history[2].delta, history[2].score, history[2].actor.clone()), (-15, 0, accounts(4)));
+
+    let page = contract.get_reputation_history(accounts(1), Some(U64(1)), Some(1));
+    assert_eq!(page, vec![history[1].clone()]);
+}
+
+#[test]
+fn test_build_and_verify_snapshot() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 5);
+    for account in [1, 3, 4] {
+        award_at(&mut context, &mut contract, accounts(account), 100);
+    }
+
+    //the build is resumed over several calls and uses the scores at the block in which it started
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(200).build());
+    let snapshot = contract.build_snapshot(Some(2));
+    assert_eq!(snapshot.root, None);
+    testing_env!(context.block_timestamp(300).build());
+    contract.penalize(accounts(3), 5);
+    let mut snapshot = contract.build_snapshot(Some(2));
+    while snapshot.root.is_none() {
+        snapshot = contract.build_snapshot(Some(2));
+    }
+    assert_eq!((snapshot.snapshot_id, snapshot.timestamp, snapshot.accounts), (0, U64(200), 3));
+    assert_eq!(
+        contract.get_snapshot_leaves(0, None, Some(1)),
+        vec![SnapshotLeaf { account_id: accounts(1), score: 20 }]
+    );
+
+    let leaf = |account: usize| crate::snapshot_leaf_hash(&accounts(account), 20);
+    let proof = |hashes: Vec<[u8; 32]>| hashes.into_iter().map(|hash| Base64VecU8(hash.to_vec())).collect::<Vec<_>>();
+    assert!(contract.verify_snapshot_proof(0, accounts(1), 20, proof(vec![leaf(3), leaf(4)])));
+    assert!(contract.verify_snapshot_proof(0, accounts(3), 20, proof(vec![leaf(1), leaf(4)])));
+    assert!(contract.verify_snapshot_proof(0, accounts(4), 20, proof(vec![crate::snapshot_node_hash(&leaf(1), &leaf(3))])));
+    assert!(!contract.verify_snapshot_proof(0, accounts(3), 15, proof(vec![leaf(1), leaf(4)])));
+    assert!(!contract.verify_snapshot_proof(1, accounts(1), 20, proof(vec![leaf(3), leaf(4)])));
+}
+
+#[test]
+fn test_snapshot_stable_across_burns() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_issuer(&mut context, &mut contract, 5);
+    for account in [1, 3, 4] {
+        award_at(&mut context, &mut contract, accounts(account), 100);
+    }
+
+    //a token burned after the build started doesn't make it skip the tokens that are left
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).block_timestamp(200).build());
+    contract.add_moderator(accounts(0));
+    contract.build_snapshot(Some(1));
+    testing_env!(context.block_timestamp(300).build());
+    contract.revoke_quest(accounts(1), 10);
+    let mut snapshot = contract.build_snapshot(Some(2));
+    while snapshot.root.is_none() {
+        snapshot = contract.build_snapshot(Some(2));
+    }
+    assert_eq!(snapshot.accounts, 3);
+}
+
+// Make quest 2 pay 10 of the token accounts(5) with a budget of 15 and fund its pool with 25
+fn setup_quest_reward(context: &mut VMContextBuilder, contract: &mut Contract) {
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(0)).build());
+    let mut quest = contract.get_quest(2).unwrap().quest;
+    quest.reward = Some(QuestReward { token_id: accounts(5), amount: U128(10), budget: U128(15) });
+    contract.update_quest(2, quest);
+
+    testing_env!(context.predecessor_account_id(accounts(5)).build());
+    match contract.ft_on_transfer(accounts(3), U128(25), "2".to_string()) {
+        PromiseOrValue::Value(refund) => assert_eq!(refund, U128(0)),
+        PromiseOrValue::Promise(_) => panic!("Expected a value"),
+    }
+}
+
+#[test]
+fn test_fund_reward_pool() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+    assert_eq!(contract.get_reward_pool(2), U128(25));
+
+    //tokens the quest doesn't pay as reward and transfers without a quest are refunded
+    for (token_id, msg) in [(accounts(4), "2"), (accounts(5), "3"), (accounts(5), "")] {
+        testing_env!(context.predecessor_account_id(token_id).build());
+        match contract.ft_on_transfer(accounts(3), U128(25), msg.to_string()) {
+            PromiseOrValue::Value(refund) => assert_eq!(refund, U128(25)),
+            PromiseOrValue::Promise(_) => panic!("Expected a value"),
+        }
+    }
+    assert_eq!(contract.get_reward_pool(2), U128(25));
+    assert_eq!(contract.get_reward_pool(3), U128(0));
+}
+
+#[test]
+fn test_withdraw_reward_pool() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+
+    testing_env!(context.attached_deposit(1).predecessor_account_id(accounts(0)).build());
+    contract.withdraw_reward_pool(2, U128(20), accounts(3));
+    assert_eq!(contract.get_reward_pool(2), U128(5));
+
+    //a failed transfer gives the amount back to the pool
+    resolve_context(&mut context, PromiseResult::Failed);
+    contract.resolve_reward_pool_withdraw(2, U128(20));
+    assert_eq!(contract.get_reward_pool(2), U128(25));
+}
+
+#[test]
+#[should_panic(expected = "Withdraw the reward pool of the quest before changing its reward token")]
+fn test_reward_token_cant_change_with_pool() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    let mut quest = contract.get_quest(2).unwrap().quest;
+    quest.reward = Some(QuestReward { token_id: accounts(4), amount: U128(10), budget: U128(15) });
+    contract.update_quest(2, quest);
+}
+
+#[test]
+fn test_gated_reward_is_held() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_human_gating(true);
+
+    //the reward is taken from the pool and held with the score
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    let held = UnclaimedReward { quest_id: Some(2), token_id: accounts(5), amount: U128(10) };
+    assert_eq!(contract.get_pending_rewards(accounts(1)), vec![held]);
+    assert_eq!(contract.get_reward_pool(2), U128(15));
+
+    //releasing the pending score transfers it
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_human_gating(false);
+    contract.release_pending_score(accounts(1));
+    assert!(contract.get_pending_rewards(accounts(1)).is_empty());
+    assert_eq!(contract.get_quest_rewards_paid(2), U128(10));
+}
+
+#[test]
+fn test_revoke_quest_returns_held_reward() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+    testing_env!(context.predecessor_account_id(accounts(0)).build());
+    contract.set_human_gating(true);
+    contract.add_moderator(accounts(4));
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+
+    testing_env!(context.attached_deposit(0).predecessor_account_id(accounts(4)).build());
+    contract.revoke_quest(accounts(1), 2);
+    assert!(contract.get_pending_rewards(accounts(1)).is_empty());
+    assert_eq!(contract.get_reward_pool(2), U128(25));
+    assert_eq!(contract.get_quest_rewards_paid(2), U128(0));
+}
+
+#[test]
+fn test_quest_reward_paid_within_budget() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+    setup_quest_reward(&mut context, &mut contract);
+
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert_eq!(contract.get_reward_pool(2), U128(15));
+    assert_eq!(contract.get_quest_rewards_paid(2), U128(10));
+
+    //a second reward would exceed the budget of the quest, so only the score is given
+    testing_env!(context
+        .storage_usage(env::storage_usage())
+        .attached_deposit(MINT_STORAGE_COST)
+        .signer_account_id(accounts(3))
+        .predecessor_account_id(accounts(3))
+        .build());
+    contract.nft_mint_quest(2);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    contract.resolve_mint_meta_token(2, accounts(3), pending_nonce(&contract, accounts(3), 2));
+    assert_eq!(contract.get_reward_pool(2), U128(15));
+    assert_eq!(contract.get_quest_rewards_paid(2), U128(10));
+    assert_eq!(contract.get_reputation(accounts(3)).score, 15);
+}
+
+#[test]
+fn test_failed_reward_can_be_claimed() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.build());
+    let mut contract = Contract::new_default_meta(accounts(0));
+
+    resolve_context(&mut context, PromiseResult::Failed);
+    contract.resolve_reward(accounts(1), Some(2), accounts(5), U128(10));
+    let unclaimed = UnclaimedReward { quest_id: Some(2), token_id: accounts(5), amount: U128(10) };
+    assert_eq!(contract.get_unclaimed_rewards(accounts(1)), vec![unclaimed]);
+
+    resolve_context(&mut context, PromiseResult::Successful(vec![]));
+    contract.resolve_reward(accounts(1), Some(2), accounts(5), U128(10));
+    assert_eq!(contract.get_unclaimed_rewards(accounts(1)).len(), 1);
+
+    testing_env!(context.predecessor_account_id(accounts(1)).build());
+    contract.claim_rewards(accounts(5));
+    assert!(contract.get_unclaimed_rewards(accounts(1)).is_empty());
+}
+
+// Write the state of the first deployment: accounts(1) holds the I'm Human token "0" and its Proof of Reputation "1",
+// with the quest information in the extra field and no index of the tokens
+fn setup_legacy_state(context: &mut VMContextBuilder) {
+    testing_env!(context.current_account_id(accounts(0)).build());
+    let mut legacy = crate::migrate::ContractV0 {
+        owner_id: accounts(0),
+        tokens_per_owner: LookupMap::new(StorageKey::TokensPerOwner.try_to_vec().unwrap()),
+        tokens_by_id: LookupMap::new(StorageKey::TokensById.try_to_vec().unwrap()),
+        token_metadata_by_id: UnorderedMap::new(StorageKey::TokenMetadataById.try_to_vec().unwrap()),
+        metadata: LazyOption::new(StorageKey::NFTContractMetadata.try_to_vec().unwrap(), None),
+    };
+    legacy.metadata.set(&NFTContractMetadata {
+        spec: "nft-1.0.0".to_string(),
+        name: "Proof of Reputation".to_string(),
+        symbol: "POR".to_string(),
+        icon: None,
+        base_uri: None,
+        reference: None,
+        reference_hash: None,
+    });
+
+    let mut owner_tokens = UnorderedSet::new(
+        StorageKey::TokenPerOwnerInner { account_id_hash: crate::internal::hash_account_id(&accounts(1)) }
+            .try_to_vec()
+            .unwrap(),
+    );
+    let legacy_tokens = [
+        ("I'm Human", "{'score':40,'date':100}"),
+        (crate::POR_TITLE, "{'score':40,'quest_completed':1,'date_last_quest':100}"),
+    ];
+    for (index, (title, extra)) in legacy_tokens.iter().enumerate() {
+        let token_id = index.to_string();
+        let token = crate::Token {
+            owner_id: accounts(1),
+            approved_account_ids: HashMap::new(),
+            next_approval_id: 0,
+            royalty: HashMap::new(),
+        };
+        let metadata = TokenMetadata { title: Some(title.to_string()), extra: Some(extra.to_string()), ..sample_token_metadata() };
+        legacy.tokens_by_id.insert(&token_id, &token);
+        legacy.token_metadata_by_id.insert(&token_id, &metadata);
+        owner_tokens.insert(&token_id);
+    }
+    legacy.tokens_per_owner.insert(&accounts(1), &owner_tokens);
+    env::state_write(&legacy);
+}
+
+#[test]
+fn test_migrate_legacy_state() {
+    let mut context = get_context(accounts(0));
+    setup_legacy_state(&mut context);
+    let mut contract = Contract::migrate();
+
+    assert_eq!(contract.get_reputation(accounts(1)).score, 40);
+    assert_eq!(contract.get_score_at(accounts(1), U64(1_000)), 0);
+    assert_eq!(contract.nft_supply_for_quest(0), U128(0));
+
+    //the indexes are backfilled in steps, the score is checkpointed from the last quest of the account
+    assert!(!contract.migrate_tokens(Some(1)));
+    assert!(contract.migrate_tokens(Some(1)));
+    assert_eq!(contract.get_score_at(accounts(1), U64(50)), 0);
+    assert_eq!(contract.get_score_at(accounts(1), U64(1_000)), 40);
+    assert_eq!(contract.nft_supply_for_quest(0), U128(1));
+    assert_eq!(contract.quest_completions.get(&0), Some(1));
+    assert_eq!(contract.nft_tokens_page(None, None).tokens.len(), 2);
+    assert_eq!(contract.nft_tokens_for_owner_page(accounts(1), None, None, None).tokens.len(), 2);
+    assert_eq!(contract.nft_tokens_by_quest(0, None, None).tokens[0].token_id, "0");
+    assert!(contract.internal_is_verified_human(&accounts(1)));
+
+    //the I'm Human quest can't be claimed again and new tokens don't reuse the old IDs
+    let claim = claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+    assert_eq!(rejected_claim(claim), ClaimOutcome::Failed { quest_id: 0, error: ClaimError::AlreadyClaimed });
+    claim_quest(&mut context, &mut contract, 2, MINT_STORAGE_COST);
+    resolve_context(&mut context, PromiseResult::Successful(b"100".to_vec()));
+    let outcome = contract.resolve_mint_meta_token(2, accounts(1), pending_nonce(&contract, accounts(1), 2));
+    assert!(matches!(outcome, ClaimOutcome::Claimed { ref token_ids, total_score: 55, .. } if token_ids == &vec!["2".to_string()]));
+}
+
+#[test]
+#[should_panic(expected = "The state is already migrated")]
+fn test_migrate_twice() {
+    let mut context = get_context(accounts(0));
+    testing_env!(context.current_account_id(accounts(0)).build());
+    let contract = Contract::new_default_meta(accounts(0));
+    env::state_write(&contract);
+    Contract::migrate();
+}
+
+#[test]
+#[should_panic(expected = "Quests can't be claimed until migrate_tokens is done")]
+fn test_claim_blocked_during_token_migration() {
+    let mut context = get_context(accounts(0));
+    setup_legacy_state(&mut context);
+    let mut contract = Contract::migrate();
+
+    //the I'm Human token of accounts(1) isn't backfilled yet
+    claim_quest(&mut context, &mut contract, 0, MINT_STORAGE_COST);
+}
+
+#[test]
+#[should_panic(expected = "Tokens can't be burned until migrate_tokens is done")]
+fn test_burn_blocked_during_token_migration() {
+    let mut context = get_context(accounts(0));
+    setup_legacy_state(&mut context);
+    let mut contract = Contract::migrate();
+
+    //the I'm Human token is backfilled but not the Proof of Reputation
+    contract.migrate_tokens(Some(1));
+    contract.add_moderator(accounts(0));
+    contract.revoke_quest(accounts(1), 0);
+}
@@ -0,0 +1,82 @@
+use crate::*;
+
+//how the IDs of the quest tokens are built
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenIdScheme {
+    //"0", "1", "2"... for every token
+    Sequential,
+    //"{quest_id}:{serial}" for quest tokens, where serial is the number of the completion
+    QuestSerial,
+}
+
+//the quest and serial number of a quest token
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenIdInfo {
+    pub quest_id: u64,
+    pub serial: u64,
+}
+
+//parse a "{quest_id}:{serial}" token ID
+pub(crate) fn parse_quest_serial(token_id: &str) -> Option<TokenIdInfo> {
+    let (quest_id, serial) = token_id.split_once(':')?;
+    Some(TokenIdInfo {
+        quest_id: quest_id.parse().ok()?,
+        serial: serial.parse().ok()?,
+    })
+}
+
+#[near_bindgen]
+impl Contract {
+    //change how the IDs of new quest tokens are built, only the owner can call it
+    pub fn set_token_id_scheme(&mut self, scheme: TokenIdScheme) {
+        self.assert_owner();
+        self.token_id_scheme = scheme;
+    }
+
+    pub fn get_token_id_scheme(&self) -> TokenIdScheme {
+        self.token_id_scheme
+    }
+
+    //get the quest and serial number of a quest token, from the ID itself for "{quest_id}:{serial}" IDs or
+    //from the metadata of the token otherwise. None if the token is not a quest token
+    pub fn parse_token_id(&self, token_id: TokenId) -> Option<TokenIdInfo> {
+        if let Some(info) = parse_quest_serial(&token_id) {
+            return Some(info);
+        }
+        let quest_id = self.internal_quest_id_of(&token_id)?;
+        let serial = self.token_metadata_by_id.get(&token_id)?.copies?;
+        Some(TokenIdInfo { quest_id, serial })
+    }
+}
+
+impl Contract {
+    //get a new token ID from the counter, skipping the IDs already taken by tokens minted with nft_mint
+    pub(crate) fn internal_next_token_id(&mut self) -> TokenId {
+        let mut counter = self.tokens_to_mint_counter.get().unwrap_or(0);
+        let mut token_id = counter.to_string();
+        while self.tokens_by_id.contains_key(&token_id) {
+            counter += 1;
+            token_id = counter.to_string();
+        }
+        self.tokens_to_mint_counter.set(&(counter + 1));
+        token_id
+    }
+
+    //get the ID of a new token of the quest with the passed in serial number
+    pub(crate) fn internal_next_quest_token_id(&mut self, quest_id: u64, serial: u64) -> TokenId {
+        match self.token_id_scheme {
+            TokenIdScheme::Sequential => self.internal_next_token_id(),
+            TokenIdScheme::QuestSerial => {
+                let token_id = format!("{}:{}", quest_id, serial);
+                if self.tokens_by_id.contains_key(&token_id) {
+                    self.internal_next_token_id()
+                } else {
+                    token_id
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use crate::*;
+
+//rules of the vouches, the bonus is a share (basis points) of the score of the voucher capped to max_bonus
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VouchConfig {
+    //minimum score an account needs to vouch for others
+    pub min_score: u64,
+    //share of the score of the voucher given as bonus, in basis points (10000 = 1x)
+    pub weight: u32,
+    //maximum bonus of a single vouch
+    pub max_bonus: u64,
+    //number of vouches an account can make in each period
+    pub quota: u64,
+    //length of the period of the quota in nanoseconds
+    pub period: U64,
+}
+
+impl Default for VouchConfig {
+    fn default() -> Self {
+        Self {
+            min_score: 50,
+            weight: 1_000,
+            max_bonus: 10,
+            quota: 3,
+            period: U64(30 * 24 * 60 * 60 * 1_000_000_000),
+        }
+    }
+}
+
+//an account vouching for another one
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vouch {
+    pub voucher_id: AccountId,
+    pub account_id: AccountId,
+    //score added to the reputation of the account
+    pub bonus: u64,
+    pub created_at: U64,
+    //block timestamp in which the vouch was revoked because the voucher was penalized
+    pub revoked_at: Option<U64>,
+}
+
+impl Vouch {
+    pub(crate) fn is_active(&self) -> bool {
+        self.revoked_at.is_none()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    //change the rules of the vouches, only the governance account (or the owner if there is none) can call it. Vouches already made keep their bonus
+    pub fn set_vouch_config(&mut self, config: VouchConfig) {
+        self.assert_governance();
+        assert!(config.period.0 > 0, "The period of the quota can't be 0");
+        self.vouch_config = config;
+    }
+
+    pub fn get_vouch_config(&self) -> VouchConfig {
+        self.vouch_config.clone()
+    }
+
+    // Method for accounts with enough reputation to vouch for another account that already has a Proof of Reputation.
+    // The account gets a bonus weighted by the score of the voucher and the vouch counts against the quota of the period.
+    // The attached deposit is added to the storage balance of the voucher, who pays the storage of the vouch
+    #[payable]
+    pub fn vouch(&mut self, account_id: AccountId) -> Vouch {
+        let initial_storage_usage = env::storage_usage();
+        let voucher_id = env::predecessor_account_id();
+        let config = self.vouch_config.clone();
+        let now = env::block_timestamp();
+
+        assert_ne!(voucher_id, account_id, "Accounts can't vouch for themselves");
+        let voucher_score = self.internal_get_por(&voucher_id).map(|(_, por)| por.score).unwrap_or(0);
+        assert!(
+            voucher_score >= config.min_score,
+            "A score of at least {} is needed to vouch for other accounts",
+            config.min_score,
+        );
+        assert!(
+            self.internal_get_por(&account_id).is_some(),
+            "The account has no reputation to vouch for"
+        );
+
+        let mut vouches_by = self.vouches_by.get(&voucher_id).unwrap_or_default();
+        assert!(
+            !vouches_by.iter().any(|vouch| vouch.account_id == account_id && vouch.is_active()),
+            "Already vouching for this account"
+        );
+        let period_start = now.saturating_sub(config.period.0);
+        let vouches_in_period = vouches_by.iter().filter(|vouch| vouch.created_at.0 > period_start).count() as u64;
+        assert!(vouches_in_period < config.quota, "The vouch quota of the period has been reached");
+
+        let deposit = env::attached_deposit();
+        if deposit > 0 {
+            self.internal_storage_deposit(&voucher_id, deposit);
+        }
+
+        let bonus = std::cmp::min(
+            (voucher_score as u128 * config.weight as u128 / DEFAULT_CATEGORY_WEIGHT as u128) as u64,
+            config.max_bonus,
+        );
+        let vouch = Vouch {
+            voucher_id: voucher_id.clone(),
+            account_id: account_id.clone(),
+            bonus,
+            created_at: U64(now),
+            revoked_at: None,
+        };
+
+        vouches_by.push(vouch.clone());
+        self.vouches_by.insert(&voucher_id, &vouches_by);
+        let mut vouches_for = self.vouches_for.get(&account_id).unwrap_or_default();
+        vouches_for.push(vouch.clone());
+        self.vouches_for.insert(&account_id, &vouches_for);
+
+        self.internal_update_por(&account_id, ReputationReason::Vouched, None, |por| por.score += bonus);
+
+        let required_storage_in_bytes = env::storage_usage() - initial_storage_usage;
+        self.internal_charge_storage(&voucher_id, required_storage_in_bytes);
+        vouch
+    }
+
+    //get the vouches received by the account, revoked vouches included
+    pub fn get_vouches_for(&self, account_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Vouch> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.vouches_for.get(&account_id).unwrap_or_default()
+            .into_iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+
+    //get the vouches made by the account, revoked vouches included
+    pub fn get_vouches_by(&self, account_id: AccountId, from_index: Option<U128>, limit: Option<u64>) -> Vec<Vouch> {
+        //where to start pagination - if we have a from_index, we'll use that - otherwise start from 0 index
+        let start = u128::from(from_index.unwrap_or(U128(0)));
+
+        self.vouches_by.get(&account_id).unwrap_or_default()
+            .into_iter()
+            //skip to the index we specified in the start variable
+            .skip(start as usize)
+            //take the first "limit" elements in the vector. If we didn't specify a limit, use 50
+            .take(limit.unwrap_or(50) as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    //revoke the active vouches made by the account and take their bonus back from the accounts that received them
+    pub(crate) fn internal_revoke_vouches_by(&mut self, voucher_id: &AccountId) {
+        let mut vouches_by = match self.vouches_by.get(voucher_id) {
+            Some(vouches) => vouches,
+            None => return,
+        };
+        let now = U64(env::block_timestamp());
+
+        for vouch in vouches_by.iter_mut().filter(|vouch| vouch.is_active()) {
+            vouch.revoked_at = Some(now);
+
+            let mut vouches_for = self.vouches_for.get(&vouch.account_id).unwrap_or_default();
+            for received in vouches_for
+                .iter_mut()
+                .filter(|received| received.voucher_id == *voucher_id && received.is_active())
+            {
+                received.revoked_at = Some(now);
+            }
+            self.vouches_for.insert(&vouch.account_id, &vouches_for);
+
+            let bonus = vouch.bonus;
+            self.internal_update_por(&vouch.account_id, ReputationReason::VouchRevoked, None, |por| por.deduct(bonus))
+                .expect("The account has no reputation");
+        }
+        self.vouches_by.insert(voucher_id, &vouches_by);
+    }
+}